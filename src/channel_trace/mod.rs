@@ -0,0 +1,117 @@
+//Jerome M. St.Martin
+//June 27, 2022
+
+//-----------------------------------------------------------------------------
+//------------- Records Every Message Sent Between the MVC Threads ------------
+//-----------------------------------------------------------------------------
+
+/* Turned on with --trace-channels. Every InputEvent, MutateCommand,
+ * DeltaNotification, CommandReply, RunState and ControlRequest is recorded as
+ * it is sent, one record per line, so the threads can be debugged without
+ * printing over the screen:
+ *
+ * seq <TAB> microseconds since start <TAB> sending thread <TAB> channel <TAB> message
+ *
+ * A RunState goes to both the TUI and the GameWorld, but is recorded once.
+ * Sequence numbers are handed out while holding the file's lock, so the file
+ * is always in sequence order. When tracing is off, record() returns at once.
+ * See viewer.rs for reading a trace back.
+ */
+
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::common::{
+    CommandReply, CommandRequest, ControlRequest, DeltaNotification, InputEvent, ModelEvent, RunState,
+};
+use crate::error::Gremlin;
+
+pub mod viewer;
+
+static TRACER: OnceLock<ChannelTracer> = OnceLock::new();
+
+///Implemented by everything sent between the MVC threads.
+pub trait Traced: Debug {
+    ///The channel name records are filed under.
+    const CHANNEL: &'static str;
+}
+
+impl Traced for InputEvent {
+    const CHANNEL: &'static str = "InputEvent";
+}
+
+impl Traced for CommandRequest {
+    const CHANNEL: &'static str = "MutateCommand";
+}
+
+impl Traced for DeltaNotification {
+    const CHANNEL: &'static str = "DeltaNotification";
+}
+
+impl Traced for CommandReply {
+    const CHANNEL: &'static str = "CommandReply";
+}
+
+impl Traced for RunState {
+    const CHANNEL: &'static str = "RunState";
+}
+
+impl Traced for ControlRequest {
+    const CHANNEL: &'static str = "ControlRequest";
+}
+
+//Only for what is not already recorded as a CommandRequest or RunState, i.e. NewGame.
+impl Traced for ModelEvent {
+    const CHANNEL: &'static str = "ModelEvent";
+}
+
+struct ChannelTracer {
+    started: Instant,
+    out: Mutex<TraceFile>,
+}
+
+struct TraceFile {
+    next_seq: u64,
+    writer: LineWriter<File>, //Flushed every record, so a crash loses nothing.
+}
+
+///Starts recording to the file, replacing any previous one. Can only be started once.
+pub fn start<P: AsRef<Path>>(path: P) -> Result<(), Gremlin> {
+    let mut writer = LineWriter::new(File::create(path)?);
+    let unix_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    writeln!(writer, "# goblin_rl channel trace, started at unix time {}", unix_secs)?;
+
+    let tracer = ChannelTracer {
+        started: Instant::now(),
+        out: Mutex::new(TraceFile { next_seq: 0, writer }),
+    };
+
+    TRACER
+        .set(tracer)
+        .map_err(|_| Gremlin::InvalidArgs("channel tracing was already started".to_string()))
+}
+
+///Called just before sending; a failure to write is not worth failing the send over.
+pub fn record<T: Traced>(message: &T) {
+    let tracer = match TRACER.get() {
+        Some(tracer) => tracer,
+        None => return,
+    };
+
+    let micros = tracer.started.elapsed().as_micros();
+    let current = thread::current();
+    let thread_name = current.name().unwrap_or("(unnamed)");
+
+    //The std Mutex is only poisoned by a panic mid-write, which leaves at worst a torn line.
+    let mut out = tracer.out.lock().unwrap_or_else(PoisonError::into_inner);
+    let seq = out.next_seq;
+    out.next_seq += 1;
+    let _ = writeln!(out.writer, "{}\t{}\t{}\t{}\t{:?}", seq, micros, thread_name, T::CHANNEL, message);
+}
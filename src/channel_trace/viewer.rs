@@ -0,0 +1,133 @@
+//Jerome M. St.Martin
+//June 27, 2022
+
+//-----------------------------------------------------------------------------
+//---------------------- Reads a Channel Trace Back ---------------------------
+//-----------------------------------------------------------------------------
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::Gremlin;
+use crate::options::TraceFilter;
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct TraceRecord {
+    pub seq: u64,
+    pub micros: u64,
+    pub thread: String,
+    pub channel: String,
+    pub message: String,
+}
+
+impl TraceRecord {
+    ///None for comments, and for lines that are not records, e.g. one torn by a crash.
+    pub fn parse(line: &str) -> Option<Self> {
+        if line.starts_with('#') {
+            return None;
+        }
+
+        let mut fields = line.splitn(5, '\t');
+        Some(TraceRecord {
+            seq: fields.next()?.parse().ok()?,
+            micros: fields.next()?.parse().ok()?,
+            thread: fields.next()?.to_string(),
+            channel: fields.next()?.to_string(),
+            message: fields.next()?.to_string(),
+        })
+    }
+
+    fn passes(&self, filter: &TraceFilter) -> bool {
+        (filter.channels.is_empty() || filter.channels.contains(&self.channel))
+            && (filter.threads.is_empty() || filter.threads.contains(&self.thread))
+    }
+}
+
+///Prints every record the filter lets through, in sequence order.
+pub fn replay<W: Write>(filter: &TraceFilter, out: &mut W) -> Result<(), Gremlin> {
+    let reader = BufReader::new(File::open(&filter.path)?);
+    let mut last_micros: Option<u64> = None;
+
+    for line in reader.lines() {
+        let record = match TraceRecord::parse(&line?) {
+            Some(record) if record.passes(filter) => record,
+            _ => continue,
+        };
+
+        if filter.paced {
+            if let Some(last) = last_micros {
+                thread::sleep(Duration::from_micros(record.micros.saturating_sub(last)));
+            }
+            last_micros = Some(record.micros);
+        }
+
+        writeln!(
+            out,
+            "{:>6} {:>12.6}s [{}] {}: {}",
+            record.seq,
+            record.micros as f64 / 1_000_000.0,
+            record.thread,
+            record.channel,
+            record.message,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(TraceRecord::parse("# goblin_rl channel trace"), None);
+        assert_eq!(TraceRecord::parse("3\t12"), None);
+        assert_eq!(
+            TraceRecord::parse("3\t1250\ttui\tMutateCommand\tCommandRequest { command: Move(N) }"),
+            Some(TraceRecord {
+                seq: 3,
+                micros: 1250,
+                thread: "tui".to_string(),
+                channel: "MutateCommand".to_string(),
+                message: "CommandRequest { command: Move(N) }".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_replay_filtered() {
+        let path = std::env::temp_dir().join(format!("goblin_rl_trace_{}.log", std::process::id()));
+        std::fs::write(
+            &path,
+            "# header\n\
+             0\t10\tmain\tInputEvent\tHjkl(N)\n\
+             1\t20\ttui\tMutateCommand\tMove(N)\n\
+             2\t30\tgameworld\tCommandReply\tAccepted\n\
+             3\t40\tmain\tInputEvent\tExit\n",
+        )
+        .unwrap();
+
+        let filter = TraceFilter {
+            path: PathBuf::from(&path),
+            channels: vec!["InputEvent".to_string(), "CommandReply".to_string()],
+            threads: vec!["main".to_string()],
+            paced: true,
+        };
+
+        let mut out: Vec<u8> = Vec::new();
+        replay(&filter, &mut out).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("[main] InputEvent: Hjkl(N)"));
+        assert!(lines[1].ends_with("[main] InputEvent: Exit"));
+    }
+}
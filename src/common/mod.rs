@@ -1,11 +1,19 @@
 //Jerome M. St.Martin
 //May, 2022
 
-use crate::error::Gremlin;
+use serde::Deserialize;
 
+use crate::error::{self, Gremlin};
+
+mod run_state;
+mod snapshot;
 mod transmittables;
+mod view_channel;
 
+pub use run_state::*;
+pub use snapshot::*;
 pub use transmittables::*;
+pub use view_channel::*;
 
 ///Used as the inner Ok() type for the various .tick() methods' returned Results.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -14,7 +22,36 @@ pub enum Ticker {
     Continue,
 }
 
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+///Implemented by the state driving each of the three threads,
+///so that all three loops treat a Gremlin the same way. See run_loop().
+pub trait TickLoop {
+    fn tick(&mut self) -> Result<Ticker, Gremlin>;
+    ///Told of every recoverable Gremlin, after it has been logged.
+    fn recover(&mut self, e: &Gremlin);
+    ///Told of the fatal Gremlin that ended the loop, after it has been logged.
+    ///Should see to it that the other threads are shut down too.
+    fn fail(&mut self, e: Gremlin);
+}
+
+///Ticks until told to exit, or until a fatal Gremlin.
+pub fn run_loop<T: TickLoop>(state: &mut T) {
+    loop {
+        match state.tick() {
+            Ok(Ticker::ExitProgram) => return,
+            Ok(Ticker::Continue) => {}
+            Err(e) => {
+                error::log_error(&e);
+                if e.is_fatal() {
+                    state.fail(e);
+                    return;
+                }
+                state.recover(&e);
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Deserialize, Debug)]
 pub enum Dir {
     N,
     NE,
@@ -26,6 +63,10 @@ pub enum Dir {
     NW,
 }
 
+impl Dir {
+    pub const ALL: [Dir; 8] = [Dir::N, Dir::NE, Dir::E, Dir::SE, Dir::S, Dir::SW, Dir::W, Dir::NW];
+}
+
 #[derive(PartialEq, Eq, Copy, Clone, Hash, Debug)]
 pub struct Coords {
     pub x: u16,
@@ -64,6 +105,19 @@ impl Coords {
         }
         Err(Gremlin::OutOfMapBounds)
     }
+    ///One step in any of the eight directions; diagonals are a step along each axis.
+    pub fn toward(c: Coords, dir: Dir, square_map_width: u16) -> Result<Coords, Gremlin> {
+        match dir {
+            Dir::N => Coords::north_of(c),
+            Dir::NE => Coords::east_of(Coords::north_of(c)?, square_map_width),
+            Dir::E => Coords::east_of(c, square_map_width),
+            Dir::SE => Coords::east_of(Coords::south_of(c, square_map_width)?, square_map_width),
+            Dir::S => Coords::south_of(c, square_map_width),
+            Dir::SW => Coords::west_of(Coords::south_of(c, square_map_width)?),
+            Dir::W => Coords::west_of(c),
+            Dir::NW => Coords::west_of(Coords::north_of(c)?),
+        }
+    }
 }
 
 
@@ -115,4 +169,17 @@ mod test {
         assert_eq!(Coords::west_of(c_ok).unwrap(), Coords::new(0u16, 0u16));
         assert!(Coords::west_of(c_err).is_err());
     }
+
+    #[test]
+    fn test_toward() {
+        let c = Coords::new(1u16, 1u16);
+
+        assert_eq!(Coords::toward(c, Dir::N, 3).unwrap(), Coords::new(1u16, 0u16));
+        assert_eq!(Coords::toward(c, Dir::NE, 3).unwrap(), Coords::new(2u16, 0u16));
+        assert_eq!(Coords::toward(c, Dir::SE, 3).unwrap(), Coords::new(2u16, 2u16));
+        assert_eq!(Coords::toward(c, Dir::SW, 3).unwrap(), Coords::new(0u16, 2u16));
+        assert_eq!(Coords::toward(c, Dir::NW, 3).unwrap(), Coords::new(0u16, 0u16));
+        assert!(Coords::toward(Coords::new(2u16, 1u16), Dir::NE, 3).is_err());
+        assert!(Coords::toward(Coords::new(1u16, 0u16), Dir::NW, 3).is_err());
+    }
 }
@@ -0,0 +1,139 @@
+//Jerome M. St.Martin
+//June 28, 2022
+
+//-----------------------------------------------------------------------------
+//------------------ The Controller's State, and its Graph --------------------
+//-----------------------------------------------------------------------------
+
+/* MainMenu --> PreRun --> MapGeneration --> AwaitingInput <--> GameWorld
+ *                              ^               ^    |           |    |
+ *                              |               |    v           |    |
+ *                              |               '--- Tui         |    |
+ *                              '------------- NextLevel <-------'    |
+ * MainMenu <------------------------------------ GameOver <----------'
+ *
+ * The Controller owns the one true RunState, and sends every change of it to
+ * the TUI and GameWorld threads. Those two may only ask the Controller for a
+ * change, which it makes only if the graph above allows it.
+ */
+
+use std::mem;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RunState {
+    AwaitingInput { previous: Box<RunState> }, //The player's turn.
+    GameOver,
+    GameWorld, //The GameWorld is carrying out the player's last command.
+    Tui,       //A TUI screen over the map has all input, e.g. a menu.
+    MainMenu,
+    MapGeneration,
+    NextLevel,
+    PreRun,
+}
+
+impl RunState {
+    pub fn awaiting_input_after(previous: RunState) -> Self {
+        RunState::AwaitingInput {
+            previous: Box::new(previous),
+        }
+    }
+
+    ///Whether the graph has an edge from self to next. AwaitingInput's previous is not considered.
+    pub fn can_become(&self, next: &RunState) -> bool {
+        use RunState::*;
+
+        matches!(
+            (self, next),
+            (MainMenu, PreRun)
+                | (PreRun, MapGeneration)
+                | (MapGeneration, AwaitingInput { .. })
+                | (AwaitingInput { .. }, GameWorld)
+                | (AwaitingInput { .. }, Tui)
+                | (Tui, AwaitingInput { .. })
+                | (GameWorld, AwaitingInput { .. })
+                | (GameWorld, NextLevel)
+                | (GameWorld, GameOver)
+                | (NextLevel, MapGeneration)
+                | (GameOver, MainMenu)
+        )
+    }
+
+    ///Same variant; AwaitingInput's previous is not considered.
+    pub fn is(&self, other: &RunState) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn all() -> Vec<RunState> {
+        vec![
+            RunState::awaiting_input_after(RunState::GameWorld),
+            RunState::GameOver,
+            RunState::GameWorld,
+            RunState::Tui,
+            RunState::MainMenu,
+            RunState::MapGeneration,
+            RunState::NextLevel,
+            RunState::PreRun,
+        ]
+    }
+
+    #[test]
+    fn test_transition_graph() {
+        let awaiting = RunState::awaiting_input_after(RunState::MapGeneration);
+        let legal = [
+            (RunState::MainMenu, RunState::PreRun),
+            (RunState::PreRun, RunState::MapGeneration),
+            (RunState::MapGeneration, awaiting.clone()),
+            (awaiting.clone(), RunState::GameWorld),
+            (awaiting.clone(), RunState::Tui),
+            (RunState::Tui, awaiting.clone()),
+            (RunState::GameWorld, awaiting.clone()),
+            (RunState::GameWorld, RunState::NextLevel),
+            (RunState::GameWorld, RunState::GameOver),
+            (RunState::NextLevel, RunState::MapGeneration),
+            (RunState::GameOver, RunState::MainMenu),
+        ];
+
+        for from in all() {
+            for to in all() {
+                let expected = legal.iter().any(|(f, t)| f.is(&from) && t.is(&to));
+                assert_eq!(from.can_become(&to), expected, "{:?} -> {:?}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_state_reachable_and_left() {
+        //Walks the graph from MainMenu; nothing may be unreachable, or a dead end.
+        let mut reached = vec![RunState::MainMenu];
+        let mut i = 0;
+        while i < reached.len() {
+            for next in all() {
+                if reached[i].can_become(&next) && !reached.iter().any(|seen| seen.is(&next)) {
+                    reached.push(next);
+                }
+            }
+            i += 1;
+        }
+        assert_eq!(reached.len(), all().len());
+
+        for state in all() {
+            assert!(all().iter().any(|next| state.can_become(next)), "{:?} is a dead end", state);
+        }
+    }
+
+    #[test]
+    fn test_is_ignores_previous() {
+        let after_tui = RunState::awaiting_input_after(RunState::Tui);
+        let after_gw = RunState::awaiting_input_after(RunState::GameWorld);
+
+        assert!(after_tui.is(&after_gw));
+        assert_ne!(after_tui, after_gw);
+        assert!(!after_tui.is(&RunState::Tui));
+    }
+}
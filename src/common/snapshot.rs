@@ -0,0 +1,153 @@
+//Jerome M. St.Martin
+//June 23, 2022
+
+//-----------------------------------------------------------------------------
+//------------------ Render Snapshots, Model -> View, Lock-Free ---------------
+//-----------------------------------------------------------------------------
+
+/* At the end of each tick the GameWorld thread copies everything the TUI needs
+ * to draw into a RenderSnapshot, and publishes it into a SnapshotSlot. The TUI
+ * thread takes the newest one whenever it likes, and draws from its own copy
+ * without ever touching the ECSAccessPoint.
+ *
+ * The slot holds at most one snapshot. Publishing swaps the new one in and
+ * drops whichever one the TUI never got around to taking; taking swaps in null.
+ * Each snapshot is only ever owned by one side at a time, so neither ever
+ * waits on the other.
+ */
+
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use specs::Entity;
+
+use super::Coords;
+
+///Everything the View needs to draw one frame, as of the end of one tick.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct RenderSnapshot {
+    pub turn: u64,
+    pub map_size: u16,
+    pub tiles: Vec<char>, //One glyph per visible tile, indexed like the Map's own Vecs.
+    pub entities: Vec<(Coords, char)>,
+    pub names: Vec<(Coords, Entity, String)>, //What the player would call each thing with a Position.
+    pub inventory: Vec<(Entity, String)>,     //What the player carries; numbered from 1 at the prompt.
+    pub log: Vec<String>, //Oldest first.
+    pub stats: SnapshotStats,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SnapshotStats {
+    pub player_at: Option<Coords>,
+    pub entity_count: usize,
+}
+
+pub struct SnapshotSlot<T: Send> {
+    newest: AtomicPtr<T>, //Null when there is nothing new to take.
+}
+
+impl<T: Send> SnapshotSlot<T> {
+    pub fn new() -> Self {
+        SnapshotSlot {
+            newest: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    ///Replaces the newest snapshot; a stale one that was never taken is dropped here.
+    pub fn publish(&self, snapshot: T) {
+        let new = Box::into_raw(Box::new(snapshot));
+        let stale = self.newest.swap(new, Ordering::AcqRel);
+        //SAFETY: every non-null pointer in the slot came from Box::into_raw(),
+        //and the swap made this the only copy of it.
+        drop(unsafe { Self::reclaim(stale) });
+    }
+
+    ///Returns the newest snapshot, or None if nothing was published since the last take.
+    pub fn take(&self) -> Option<Box<T>> {
+        let newest = self.newest.swap(ptr::null_mut(), Ordering::AcqRel);
+        //SAFETY: as in publish().
+        unsafe { Self::reclaim(newest) }
+    }
+
+    unsafe fn reclaim(raw: *mut T) -> Option<Box<T>> {
+        if raw.is_null() {
+            None
+        } else {
+            Some(Box::from_raw(raw))
+        }
+    }
+}
+
+impl<T: Send> Default for SnapshotSlot<T> {
+    fn default() -> Self {
+        SnapshotSlot::new()
+    }
+}
+
+impl<T: Send> Drop for SnapshotSlot<T> {
+    fn drop(&mut self) {
+        drop(self.take());
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    struct Counted(u64, Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.1.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_take_newest_and_drop_stale() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let slot: SnapshotSlot<Counted> = SnapshotSlot::new();
+        assert!(slot.take().is_none());
+
+        slot.publish(Counted(1, dropped.clone()));
+        slot.publish(Counted(2, dropped.clone()));
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+
+        let newest = slot.take().unwrap();
+        assert_eq!(newest.0, 2);
+        assert!(slot.take().is_none());
+        drop(newest);
+        assert_eq!(dropped.load(Ordering::SeqCst), 2);
+
+        slot.publish(Counted(3, dropped.clone()));
+        drop(slot);
+        assert_eq!(dropped.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_snapshots_across_threads() {
+        let slot: Arc<SnapshotSlot<u64>> = Arc::new(SnapshotSlot::new());
+
+        let gw_slot = slot.clone();
+        let publisher = thread::spawn(move || {
+            for turn in 1..=10_000 {
+                gw_slot.publish(turn);
+            }
+        });
+
+        //Turns may be skipped, but never seen out of order.
+        let mut last_seen = 0;
+        while last_seen < 10_000 {
+            if let Some(turn) = slot.take() {
+                assert!(*turn > last_seen);
+                last_seen = *turn;
+            }
+        }
+
+        publisher.join().unwrap();
+    }
+}
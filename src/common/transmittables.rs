@@ -5,20 +5,40 @@
 //--------------------- Things that Get Sent via Channels ---------------------
 //-----------------------------------------------------------------------------
 
-use super::Dir;
+use std::fmt;
+
+use serde::Deserialize;
+use specs::Entity;
+
+use super::{Coords, Dir, RunState, ViewSender};
 
 //---------------------- Controller -> View ----------------------
 ///Commands passed from Controller to View (in MVC) via mpsc::channels.
-#[derive(Clone, PartialEq, Eq, Debug)]
+///Deserialize, so that a keybindings file can name them.
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug)]
 pub enum InputEvent {
     Hjkl(Dir),
     Wasd(Dir),
+    Run(Dir),    //Step after step, until something interesting.
+    Attack(Dir), //At the adjacent tile, never stepping onto it.
+    Count(u8),   //A digit of a count prefix, as in 5j.
+    Go,          //Prefix key: the next direction runs.
+    Fight,       //Prefix key: the next direction attacks.
+    Prompt,      //Opens the command prompt.
+    Repeat { count: u16, input: Box<InputEvent> }, //A count prefix, and what it was for.
     Cancel,
     Confirm,
     Tab,
     BackTab,
     Delete,
     Menu,
+    Click { column: u16, row: u16 }, //Left mouse button, at a position on the screen.
+    Hover { column: u16, row: u16 }, //The mouse moved to a position on the screen.
+    Resize { columns: u16, rows: u16 }, //The terminal's new size.
+    Char(char), //Only in text entry, as typed.
+    Backspace,  //Only in text entry; otherwise Backspace is Delete.
+    Home,
+    End,
     Null,
     Exit, //Used to end the program
 }
@@ -26,11 +46,152 @@ pub enum InputEvent {
 
 //---------------------- View -> Model ---------------------
 ///Commands passed from View to Model (in MVC) via mpsc::channels.
-#[derive(PartialEq, Eq, Debug)]
+///Player actions all act on behalf of the entity with the Player component.
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub enum MutateCommand {
-    Test,
+    Move(Dir),
+    Run(Dir),                    //Moves until refused, or until next to something.
+    Attack(Dir),                 //Whatever stands on the adjacent tile.
+    Wait,
+    PickUp,                      //Whatever Item lies on the player's tile.
+    Drop(Entity),                //An Item the player is carrying.
+    Use(Entity, Option<Target>), //An Item the player is carrying; given a Target only if Aimed.
+    Descend,
+    Ascend,
+    Interact(Dir),               //With whatever is on the adjacent tile, e.g. a Door.
+    Travel(Coords),              //Along a shortest path there, a turn per step.
+    Repeat(u16, Box<MutateCommand>), //That many times, a turn each, until one is refused.
     Exit,
 }
+
+///What actually travels from View to Model: a MutateCommand, plus where to send
+///the CommandReply, if anywhere. A plain MutateCommand converts into one with no reply.
+#[derive(Debug)]
+pub struct CommandRequest {
+    pub command: MutateCommand,
+    pub reply_to: Option<ViewSender<CommandReply>>, //Unbounded, so the Model never waits to reply.
+}
+
+impl CommandRequest {
+    pub fn with_reply(command: MutateCommand, reply_to: ViewSender<CommandReply>) -> Self {
+        CommandRequest {
+            command,
+            reply_to: Some(reply_to),
+        }
+    }
+}
+
+impl From<MutateCommand> for CommandRequest {
+    fn from(command: MutateCommand) -> Self {
+        CommandRequest {
+            command,
+            reply_to: None,
+        }
+    }
+}
+
+///Everything the Model's one Receiver gets: commands from the View, and from the
+///Controller, every change of RunState. One channel keeps the two in send order.
+#[derive(Debug)]
+pub enum ModelEvent {
+    Command(CommandRequest),
+    RunState(RunState),
+    NewGame { seed: u64 }, //From the View's title screen, just before it asks for PreRun.
+}
+
+impl From<CommandRequest> for ModelEvent {
+    fn from(request: CommandRequest) -> Self {
+        ModelEvent::Command(request)
+    }
+}
+
+impl From<MutateCommand> for ModelEvent {
+    fn from(command: MutateCommand) -> Self {
+        ModelEvent::Command(command.into())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Target {
+    Entity(Entity),
+    Tile(Coords),
+}
+
+///Why the Model refused a MutateCommand. Displays as a message for the player.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Rejection {
+    NoPlayer, //There is no entity with the Player component to act.
+    OutOfBounds,
+    Wall,
+    Blocked,
+    NothingToPickUp,
+    NotCarried(Entity),
+    NotUsable(Entity),
+    NeedsTarget(Entity), //Aimed, but used on nothing.
+    NotAimed(Entity),    //Used on something, but cannot be aimed.
+    InvalidTarget,
+    NoStairsDown,
+    NoStairsUp,
+    NothingToInteractWith,
+    NoPath,
+    NothingToAttack,
+    CannotRepeat, //Repeated no times, or not a command that can be repeated.
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Rejection::NoPlayer => "There is nobody to do that.",
+            Rejection::OutOfBounds => "You cannot leave the map.",
+            Rejection::Wall => "You bump into a wall.",
+            Rejection::Blocked => "Something is in the way.",
+            Rejection::NothingToPickUp => "There is nothing here to pick up.",
+            Rejection::NotCarried(_) => "You are not carrying that.",
+            Rejection::NotUsable(_) => "You cannot use that.",
+            Rejection::NeedsTarget(_) => "You must use that on something.",
+            Rejection::NotAimed(_) => "You cannot use that on anything.",
+            Rejection::InvalidTarget => "That is not a valid target.",
+            Rejection::NoStairsDown => "There are no stairs down here.",
+            Rejection::NoStairsUp => "There are no stairs up here.",
+            Rejection::NothingToInteractWith => "There is nothing there to interact with.",
+            Rejection::NoPath => "You cannot find a way there.",
+            Rejection::NothingToAttack => "There is nothing there to attack.",
+            Rejection::CannotRepeat => "You cannot repeat that.",
+        };
+
+        write!(f, "{}", message)
+    }
+}
+//------------------------ ------------- ------------------------
+
+//---------------------- Model -> View, Replies ---------------------
+///Sent back to whoever attached a reply handle to a CommandRequest, once the
+///tick that carried the command out is over. The deltas are everything that tick
+///changed; they are sent here instead of as separate DeltaNotifications.
+#[derive(PartialEq, Eq, Debug)]
+pub struct CommandReply {
+    pub outcome: CommandOutcome,
+    pub deltas: Vec<DeltaNotification>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CommandOutcome {
+    Accepted,
+    Rejected(Rejection),
+}
+//------------------------ ------------- ------------------------
+
+//---------------- View & Model -> Controller --------------------
+///Asked of the Controller, which alone decides what actually happens.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ControlRequest {
+    Transition(RunState), //Made only if the RunState graph allows it.
+    Quit,
+    Shutdown(String),     //The sender hit a fatal Gremlin, described here, and has stopped.
+    //Whether keys are typed text, rather than looked up in the keybindings,
+    //as of the TUI having handled that many InputEvents; sent after every one.
+    TextEntry { on: bool, inputs: u64 },
+}
 //------------------------ ------------- ------------------------
 
 //------------------------ Model -> View ------------------------
@@ -38,7 +199,10 @@ pub enum MutateCommand {
 ///i.e. The Model telling the View: "Here's what changed in the Game World".
 #[derive(PartialEq, Eq, Debug)]
 pub enum DeltaNotification {
-    MapDirty,
+    //Sent automatically, from ECSAccessPoint::collect_deltas()
+    EntityMoved { entity: Entity, from: Coords, to: Coords },
+    ComponentAdded { entity: Entity, component: &'static str },
+    ComponentRemoved { entity: Entity, component: &'static str },
 }
 
 //------------------------ ------------- ------------------------
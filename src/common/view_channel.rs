@@ -0,0 +1,91 @@
+//Jerome M. St.Martin
+//June 26, 2022
+
+//-----------------------------------------------------------------------------
+//------------- One Channel into the View, from Several Producers -------------
+//-----------------------------------------------------------------------------
+
+/* The TUI thread has to react to controller input, model deltas, replies, and
+ * changes of RunState, whichever comes first. std::sync::mpsc cannot wait on
+ * several Receivers at once, so all of them are sent down the same channel,
+ * wrapped in a ViewEvent, and the TUI waits on its one Receiver.
+ *
+ * Each producer still only gets a ViewSender for the one kind of thing it
+ * sends, and a failed send hands back that same thing, as a plain Sender would.
+ * The channel is unbounded, so no producer ever waits on the TUI.
+ */
+
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+
+use super::{CommandReply, DeltaNotification, InputEvent, RunState};
+use crate::channel_trace::{self, Traced};
+
+#[derive(Debug)]
+pub enum ViewEvent {
+    Input(InputEvent),
+    Delta(DeltaNotification),
+    Reply(CommandReply),
+    RunState(RunState),
+}
+
+///Implemented by everything that can be sent to the View.
+pub trait IntoViewEvent: Traced + Sized {
+    fn into_view_event(self) -> ViewEvent;
+    ///Only ever called on a ViewEvent made by into_view_event().
+    fn from_view_event(event: ViewEvent) -> Self;
+}
+
+pub fn view_channel() -> (Sender<ViewEvent>, Receiver<ViewEvent>) {
+    mpsc::channel()
+}
+
+#[derive(Debug)]
+pub struct ViewSender<T: IntoViewEvent> {
+    tx: Sender<ViewEvent>,
+    sends: std::marker::PhantomData<fn(T)>,
+}
+
+impl<T: IntoViewEvent> ViewSender<T> {
+    pub fn new(tx: Sender<ViewEvent>) -> Self {
+        ViewSender {
+            tx,
+            sends: std::marker::PhantomData,
+        }
+    }
+
+    pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+        channel_trace::record(&item);
+        self.tx
+            .send(item.into_view_event())
+            .map_err(|SendError(event)| SendError(T::from_view_event(event)))
+    }
+}
+
+//Derived Clone would needlessly require T: Clone.
+impl<T: IntoViewEvent> Clone for ViewSender<T> {
+    fn clone(&self) -> Self {
+        ViewSender::new(self.tx.clone())
+    }
+}
+
+macro_rules! impl_into_view_event {
+    ($t:ty, $variant:ident) => {
+        impl IntoViewEvent for $t {
+            fn into_view_event(self) -> ViewEvent {
+                ViewEvent::$variant(self)
+            }
+
+            fn from_view_event(event: ViewEvent) -> Self {
+                match event {
+                    ViewEvent::$variant(item) => item,
+                    other => unreachable!("ViewSender got back a {:?}", other),
+                }
+            }
+        }
+    };
+}
+
+impl_into_view_event!(InputEvent, Input);
+impl_into_view_event!(DeltaNotification, Delta);
+impl_into_view_event!(CommandReply, Reply);
+impl_into_view_event!(RunState, RunState);
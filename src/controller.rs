@@ -2,96 +2,533 @@
 //May, 2022
 
 use std::any::Any;
-use std::sync::{mpsc::SyncSender, Arc};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, RecvError, RecvTimeoutError, SyncSender};
+use std::sync::Arc;
 use std::thread::JoinHandle;
+use std::time::Duration;
 
-use crate::common::{InputEvent, Ticker};
+use crate::channel_trace;
+use crate::common::{CommandRequest, ControlRequest, InputEvent, ModelEvent, MutateCommand, RunState, TickLoop, Ticker, ViewSender};
 use crate::ecs_access_point::ECSAccessPoint;
 use crate::error::Gremlin;
-use crate::user_input::UserInput;
+use crate::user_input::{Keybindings, UserInput};
 
 //-------------------------------------------
 //--------------- CONTROLLER ----------------
 //----------------- of MVC ------------------
 //-------------------------------------------
 
-type ResTuple = (
-    Result<(), Box<dyn Any + Send>>,
-    Result<(), Box<dyn Any + Send>>,
-);
-
-#[derive(Clone, Debug)]
-pub enum RunState {
-    AwaitingInput { previous: Box<RunState> },
-    GameOver,
-    GameWorld,
-    Tui,
-    MainMenu,
-    MapGeneration,
-    NextLevel,
-    PreRun,
-}
+//How long tick() waits for input, before checking again for RunState changes asked for.
+const INPUT_POLL: Duration = Duration::from_millis(20);
 
 pub struct MainState {
     game_world: JoinHandle<()>, //Game Simulation State
     tui: JoinHandle<()>,        //GUI State
-    tui_tx: SyncSender<InputEvent>,
+    tui_tx: ViewSender<InputEvent>,
+    routed: u64,   //InputEvents sent to the TUI so far.
+    answered: u64, //How many of those the TUI had handled, when it last said whether it wants text.
+    runstate_tx: ViewSender<RunState>, //Every change of RunState, to the TUI...
+    model_tx: SyncSender<ModelEvent>,  //...and to the GameWorld.
+    control_rx: Receiver<ControlRequest>, //Asked of the Controller by the TUI and GameWorld.
+    user_input: UserInput,
+    deferred: VecDeque<InputEvent>,    //Input held while the GameWorld finishes a turn.
     ecs_ap: Arc<ECSAccessPoint>,
     runstate: RunState,
+    shutdown: Option<String>, //Why the program is stopping, if it was not asked to.
 }
 
 impl MainState {
     pub fn new(
         game_world: JoinHandle<()>,
         tui: JoinHandle<()>,
-        tui_tx: SyncSender<InputEvent>,
+        tui_tx: ViewSender<InputEvent>,
+        runstate_tx: ViewSender<RunState>,
+        model_tx: SyncSender<ModelEvent>,
+        control_rx: Receiver<ControlRequest>,
         ecs_ap: Arc<ECSAccessPoint>,
     ) -> MainState {
         MainState {
             game_world,
             tui,
             tui_tx,
+            routed: 0,
+            answered: 0,
+            runstate_tx,
+            model_tx,
+            control_rx,
+            user_input: UserInput::default(),
+            deferred: VecDeque::new(),
             ecs_ap,
             runstate: RunState::MainMenu,
+            shutdown: None,
+        }
+    }
+
+    ///Replaces the built-in keybindings.
+    pub fn with_keybindings(mut self, keybindings: Keybindings) -> Self {
+        self.user_input = UserInput::new(keybindings);
+        self
+    }
+
+    //Carried out in the order asked, before any more input is routed.
+    fn apply_requests(&mut self) -> Result<Ticker, Gremlin> {
+        while let Ok(request) = self.control_rx.try_recv() {
+            if self.apply(request)? == Ticker::ExitProgram {
+                return Ok(Ticker::ExitProgram);
+            }
         }
+        Ok(Ticker::Continue)
     }
 
-    pub fn tick(&mut self) -> Result<Ticker, Gremlin> {
-        let user_input: InputEvent = UserInput::blocking_read()?;
+    fn apply(&mut self, request: ControlRequest) -> Result<Ticker, Gremlin> {
+        match request {
+            ControlRequest::Transition(next) => self.transition(next)?,
+            ControlRequest::Quit => return MainState::pre_exit(&self.tui_tx),
+            ControlRequest::TextEntry { on, inputs } => {
+                self.user_input.set_text_entry(on);
+                self.answered = inputs;
+            }
+            ControlRequest::Shutdown(reason) => {
+                self.shut_down(reason);
+                return Ok(Ticker::ExitProgram);
+            }
+        }
+        Ok(Ticker::Continue)
+    }
+
+    //Whether the next key can be translated yet. Any input still held, or not yet
+    //answered by the TUI, may be the one that opens or closes a text field.
+    fn caught_up(&self) -> bool {
+        self.answered >= self.routed && self.deferred.is_empty()
+    }
+
+    fn pass_to_tui(&mut self, user_input: InputEvent) -> Result<(), Gremlin> {
+        self.tui_tx.send(user_input)?;
+        self.routed += 1;
+        Ok(())
+    }
 
+    fn route(&mut self, user_input: InputEvent) -> Result<Ticker, Gremlin> {
         if user_input == InputEvent::Exit {
             //Gracefully Exit Program
-            MainState::pre_exit(&self.tui_tx)?;
-            return Ok(Ticker::ExitProgram);
+            return MainState::pre_exit(&self.tui_tx);
         };
+        //Only changes what is drawn, so is never held back, whatever the RunState.
+        if let InputEvent::Hover { .. } | InputEvent::Resize { .. } = user_input {
+            self.pass_to_tui(user_input)?;
+            return Ok(Ticker::Continue);
+        }
 
-        //Pass user input through to TUI thread
-        self.tui_tx.send(user_input)?;
-
-        match &self.runstate {
-            RunState::AwaitingInput { previous: _prev } => {}
-            RunState::GameOver => {}
-            RunState::GameWorld => {}
-            RunState::Tui => {}
-            RunState::MainMenu => {}
-            RunState::MapGeneration => {}
-            RunState::NextLevel => {}
-            RunState::PreRun => {}
+        match self.runstate {
+            //The TUI's title screen asks for PreRun, or to Quit, once the player has chosen.
+            RunState::MainMenu => self.pass_to_tui(user_input)?,
+            RunState::AwaitingInput { .. } => match user_input {
+                InputEvent::Menu => self.transition(RunState::Tui)?,
+                //Pass user input through to TUI thread
+                _ => self.pass_to_tui(user_input)?,
+            },
+            RunState::Tui => match user_input {
+                InputEvent::Cancel => self.transition(RunState::awaiting_input_after(RunState::Tui))?,
+                _ => self.pass_to_tui(user_input)?,
+            },
+            RunState::GameOver => {
+                if let InputEvent::Confirm | InputEvent::Cancel = user_input {
+                    self.transition(RunState::MainMenu)?;
+                }
+            }
+            //Meant for the next turn. PreRun, MapGeneration and NextLevel are
+            //left as soon as they are entered, so are never seen here.
+            RunState::GameWorld | RunState::PreRun | RunState::MapGeneration | RunState::NextLevel => {
+                self.deferred.push_back(user_input);
+            }
         }
 
         Ok(Ticker::Continue)
     }
 
-    //Used to stop the two main threads upon Game Over or Game Close
-    pub(crate) fn join_threads(self) -> ResTuple {
-        //Returns two results.
-        (self.game_world.join(), self.tui.join())
+    ///Changes RunState, if the graph in run_state.rs allows it, then tells the
+    ///other threads and runs the hooks. Asking for the current state changes nothing.
+    fn transition(&mut self, next: RunState) -> Result<(), Gremlin> {
+        if self.runstate.is(&next) {
+            return Ok(());
+        }
+        if !self.runstate.can_become(&next) {
+            return Err(Gremlin::IllegalTransition(self.runstate.clone(), next));
+        }
+
+        //Whoever asked, AwaitingInput records the state actually left.
+        let next = match next {
+            RunState::AwaitingInput { .. } => RunState::awaiting_input_after(self.runstate.clone()),
+            other => other,
+        };
+
+        self.on_exit(&next);
+        self.runstate = next;
+        self.send_runstate()?;
+        self.on_enter()
+    }
+
+    fn on_exit(&mut self, next: &RunState) {
+        //Held input is for a next turn on this level, which will not come.
+        if self.runstate == RunState::GameWorld && !matches!(next, RunState::AwaitingInput { .. }) {
+            self.deferred.clear();
+        }
     }
 
-    fn pre_exit(tui_tx: &SyncSender<InputEvent>) -> Result<Ticker, Gremlin> {
+    fn on_enter(&mut self) -> Result<(), Gremlin> {
+        match self.runstate {
+            RunState::PreRun | RunState::NextLevel => self.transition(RunState::MapGeneration),
+            //The GameWorld builds the level before handling any command sent after this.
+            RunState::MapGeneration => self.transition(RunState::awaiting_input_after(RunState::MapGeneration)),
+            RunState::AwaitingInput { .. } => {
+                //Routed as if it had only just arrived; Exit is never held.
+                while let Some(user_input) = self.deferred.pop_front() {
+                    self.route(user_input)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    //Recorded once, by the ViewSender, for both sends.
+    fn send_runstate(&self) -> Result<(), Gremlin> {
+        self.runstate_tx.send(self.runstate.clone())?;
+        self.model_tx.send(ModelEvent::RunState(self.runstate.clone()))?;
+        Ok(())
+    }
+
+    //A thread that stopped without being told to has either failed, and asked
+    //for a Shutdown first, or panicked.
+    fn check_threads(&self) -> Result<(), Gremlin> {
+        if self.game_world.is_finished() {
+            return Err(Gremlin::ThreadStopped("gameworld"));
+        }
+        if self.tui.is_finished() {
+            return Err(Gremlin::ThreadStopped("tui"));
+        }
+        Ok(())
+    }
+
+    //Tells both other threads to finish, directly, since either may be the one
+    //that failed. Sends fail only to threads that are already gone.
+    fn shut_down(&mut self, reason: String) {
+        self.shutdown.get_or_insert(reason);
+        let _ = self.tui_tx.send(InputEvent::Exit);
+
+        let request: CommandRequest = MutateCommand::Exit.into();
+        channel_trace::record(&request);
+        let _ = self.model_tx.send(request.into());
+    }
+
+    ///Waits for the other two threads to finish, then writes the access report if asked to.
+    ///Returns what went wrong, if anything, one line each: why the program shut down,
+    ///and any thread that panicked.
+    pub(crate) fn join_threads(self, report_path: Option<&Path>) -> Vec<String> {
+        let mut problems: Vec<String> = self.shutdown.into_iter().collect();
+
+        for (name, handle) in [("gameworld", self.game_world), ("tui", self.tui)] {
+            if let Err(payload) = handle.join() {
+                problems.push(format!("The {} thread panicked: {}", name, panic_message(&payload)));
+            }
+        }
+
+        //Both threads are done with the ECS by now, so the lock contention stats are final.
+        if let Some(report_path) = report_path {
+            if let Err(e) = self.ecs_ap.write_access_report(report_path) {
+                problems.push(format!("Could not write {}: {}", report_path.display(), e));
+            }
+        }
+
+        problems
+    }
+
+    fn pre_exit(tui_tx: &ViewSender<InputEvent>) -> Result<Ticker, Gremlin> {
         //Tell TUI thread to finish
         tui_tx.send(InputEvent::Exit)?;
 
         Ok(Ticker::ExitProgram)
     }
 }
+
+impl TickLoop for MainState {
+    fn tick(&mut self) -> Result<Ticker, Gremlin> {
+        if self.apply_requests()? == Ticker::ExitProgram {
+            return Ok(Ticker::ExitProgram);
+        }
+        self.check_threads()?;
+
+        //Keys wait, unread, until they can be translated in the right mode.
+        if !self.caught_up() {
+            return match self.control_rx.recv_timeout(INPUT_POLL) {
+                Ok(request) => self.apply(request),
+                Err(RecvTimeoutError::Timeout) => Ok(Ticker::Continue),
+                Err(RecvTimeoutError::Disconnected) => Err(Gremlin::RecvErr(RecvError)),
+            };
+        }
+
+        match self.user_input.read_timeout(INPUT_POLL)? {
+            Some(user_input) => self.route(user_input),
+            None => Ok(Ticker::Continue),
+        }
+    }
+
+    //Already logged, and there is nowhere to show it; the TUI has the screen.
+    fn recover(&mut self, _e: &Gremlin) {}
+
+    fn fail(&mut self, e: Gremlin) {
+        self.shut_down(format!("The controller stopped: {}", e));
+    }
+}
+
+//panic!() with a message gives a &str or a String; anything else is rare.
+fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "(no message)"
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::sync::mpsc::{self, Sender};
+    use std::thread;
+
+    use specs::WorldExt;
+
+    use super::*;
+    use crate::common::{view_channel, Dir, ViewEvent};
+
+    struct Ends {
+        view_rx: Receiver<ViewEvent>,
+        model_rx: Receiver<ModelEvent>,
+        control_tx: Sender<ControlRequest>,
+    }
+
+    fn test_controller() -> (MainState, Ends) {
+        let (view_tx, view_rx) = view_channel();
+        let (model_tx, model_rx) = mpsc::sync_channel(64); //Nothing drains it until asked.
+        let (control_tx, control_rx) = mpsc::channel();
+
+        let ms = MainState::new(
+            thread::spawn(|| {}),
+            thread::spawn(|| {}),
+            ViewSender::new(view_tx.clone()),
+            ViewSender::new(view_tx),
+            model_tx,
+            control_rx,
+            Arc::new(ECSAccessPoint::new(WorldExt::new())),
+        );
+
+        (ms, Ends { view_rx, model_rx, control_tx })
+    }
+
+    fn ask(ends: &Ends, next: RunState) {
+        ends.control_tx.send(ControlRequest::Transition(next)).unwrap();
+    }
+
+    //The title screen's New Game.
+    fn start(ms: &mut MainState, ends: &Ends) {
+        ask(ends, RunState::PreRun);
+        assert_eq!(ms.apply_requests().unwrap(), Ticker::Continue);
+    }
+
+    //Everything sent to the TUI since last asked, as (RunStates, InputEvents).
+    fn to_tui(ends: &Ends) -> (Vec<RunState>, Vec<InputEvent>) {
+        let (mut states, mut inputs) = (Vec::new(), Vec::new());
+        for event in ends.view_rx.try_iter() {
+            match event {
+                ViewEvent::RunState(state) => states.push(state),
+                ViewEvent::Input(input) => inputs.push(input),
+                other => panic!("the Controller sent a {:?}", other),
+            }
+        }
+        (states, inputs)
+    }
+
+    #[test]
+    fn test_new_game() {
+        let (mut ms, ends) = test_controller();
+
+        ms.route(InputEvent::Hjkl(Dir::S)).unwrap();
+        ms.route(InputEvent::Confirm).unwrap();
+        assert_eq!(ms.runstate, RunState::MainMenu);
+        assert_eq!(to_tui(&ends), (vec![], vec![InputEvent::Hjkl(Dir::S), InputEvent::Confirm]));
+
+        start(&mut ms, &ends);
+        let expected = vec![
+            RunState::PreRun,
+            RunState::MapGeneration,
+            RunState::awaiting_input_after(RunState::MapGeneration),
+        ];
+        assert_eq!(ms.runstate, expected[2]);
+        assert_eq!(to_tui(&ends), (expected.clone(), vec![]));
+
+        let to_model: Vec<RunState> = ends
+            .model_rx
+            .try_iter()
+            .map(|event| match event {
+                ModelEvent::RunState(state) => state,
+                other => panic!("the Controller sent a {:?}", other),
+            })
+            .collect();
+        assert_eq!(to_model, expected);
+    }
+
+    #[test]
+    fn test_turns_hold_input() {
+        let (mut ms, ends) = test_controller();
+        start(&mut ms, &ends);
+        to_tui(&ends);
+
+        //The TUI asks for GameWorld when it sends a command; input then waits for the turn to end.
+        ask(&ends, RunState::GameWorld);
+        ask(&ends, RunState::GameWorld); //Asking twice is harmless.
+        ms.apply_requests().unwrap();
+        ms.route(InputEvent::Hjkl(Dir::N)).unwrap();
+        ms.route(InputEvent::Hjkl(Dir::E)).unwrap();
+        ms.route(InputEvent::Hover { column: 3, row: 4 }).unwrap(); //Never held.
+        assert_eq!(
+            to_tui(&ends),
+            (vec![RunState::GameWorld], vec![InputEvent::Hover { column: 3, row: 4 }])
+        );
+
+        ask(&ends, RunState::awaiting_input_after(RunState::MainMenu));
+        ms.apply_requests().unwrap();
+        assert_eq!(ms.runstate, RunState::awaiting_input_after(RunState::GameWorld));
+        assert_eq!(
+            to_tui(&ends),
+            (
+                vec![RunState::awaiting_input_after(RunState::GameWorld)],
+                vec![InputEvent::Hjkl(Dir::N), InputEvent::Hjkl(Dir::E)]
+            )
+        );
+
+        //Menus over the map take all input until cancelled.
+        ms.route(InputEvent::Menu).unwrap();
+        ms.route(InputEvent::Tab).unwrap();
+        ms.route(InputEvent::Cancel).unwrap();
+        assert_eq!(ms.runstate, RunState::awaiting_input_after(RunState::Tui));
+        assert_eq!(to_tui(&ends).1, vec![InputEvent::Tab]);
+    }
+
+    #[test]
+    fn test_level_change_and_game_over() {
+        let (mut ms, ends) = test_controller();
+        start(&mut ms, &ends);
+
+        //NextLevel passes straight through MapGeneration back to the player's turn.
+        ask(&ends, RunState::GameWorld);
+        ask(&ends, RunState::NextLevel);
+        ms.apply_requests().unwrap();
+        assert_eq!(ms.runstate, RunState::awaiting_input_after(RunState::MapGeneration));
+
+        //Held input does not outlive the turn it was held through.
+        ask(&ends, RunState::GameWorld);
+        ms.apply_requests().unwrap();
+        ms.route(InputEvent::Hjkl(Dir::W)).unwrap();
+        assert!(!ms.caught_up()); //Not a key more until the held one has gone to the TUI.
+        ask(&ends, RunState::GameOver);
+        ms.apply_requests().unwrap();
+        assert!(ms.deferred.is_empty());
+
+        ms.route(InputEvent::Hjkl(Dir::W)).unwrap();
+        assert_eq!(ms.runstate, RunState::GameOver);
+        ms.route(InputEvent::Confirm).unwrap();
+        assert_eq!(ms.runstate, RunState::MainMenu);
+        assert!(!to_tui(&ends).1.contains(&InputEvent::Hjkl(Dir::W)));
+
+        assert_eq!(ms.route(InputEvent::Exit).unwrap(), Ticker::ExitProgram);
+    }
+
+    #[test]
+    fn test_quit_request() {
+        let (mut ms, ends) = test_controller();
+
+        ends.control_tx.send(ControlRequest::Quit).unwrap();
+        ask(&ends, RunState::PreRun); //Never made; the Controller is done by then.
+        assert_eq!(ms.apply_requests().unwrap(), Ticker::ExitProgram);
+        assert_eq!(ms.runstate, RunState::MainMenu);
+        assert_eq!(to_tui(&ends).1, vec![InputEvent::Exit]);
+    }
+
+    #[test]
+    fn test_text_entry_request() {
+        let (mut ms, ends) = test_controller();
+        assert!(ms.caught_up());
+
+        //No more keys are read until the TUI has answered for every input sent to it.
+        ms.route(InputEvent::Confirm).unwrap();
+        ms.route(InputEvent::Hover { column: 1, row: 1 }).unwrap();
+        assert!(!ms.caught_up());
+        ends.control_tx.send(ControlRequest::TextEntry { on: true, inputs: 1 }).unwrap();
+        ms.apply_requests().unwrap();
+        assert!(ms.user_input.text_entry());
+        assert!(!ms.caught_up());
+        ends.control_tx.send(ControlRequest::TextEntry { on: true, inputs: 2 }).unwrap();
+        ms.apply_requests().unwrap();
+        assert!(ms.caught_up());
+
+        ends.control_tx.send(ControlRequest::TextEntry { on: false, inputs: 2 }).unwrap();
+        ms.apply_requests().unwrap();
+        assert!(!ms.user_input.text_entry());
+    }
+
+    #[test]
+    fn test_illegal_request() {
+        let (mut ms, ends) = test_controller();
+
+        ask(&ends, RunState::GameOver);
+        assert!(matches!(
+            ms.apply_requests(),
+            Err(Gremlin::IllegalTransition(RunState::MainMenu, RunState::GameOver))
+        ));
+        assert_eq!(ms.runstate, RunState::MainMenu);
+        assert!(ends.model_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let (mut ms, ends) = test_controller();
+        ms.game_world = thread::spawn(|| panic!("the map is on fire"));
+
+        ends.control_tx.send(ControlRequest::Shutdown("The tui thread stopped: RecvErr".to_string())).unwrap();
+        assert_eq!(ms.apply_requests().unwrap(), Ticker::ExitProgram);
+        //Both threads are told to finish.
+        assert_eq!(to_tui(&ends).1, vec![InputEvent::Exit]);
+        assert!(matches!(
+            ends.model_rx.try_recv(),
+            Ok(ModelEvent::Command(request)) if request.command == MutateCommand::Exit
+        ));
+
+        let report_path = std::env::temp_dir().join(format!("goblin_rl_access_{}.txt", std::process::id()));
+        let problems = ms.join_threads(Some(&report_path));
+        std::fs::remove_file(&report_path).unwrap();
+        assert_eq!(
+            problems,
+            vec![
+                "The tui thread stopped: RecvErr".to_string(),
+                "The gameworld thread panicked: the map is on fire".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_threads() {
+        let (mut ms, _ends) = test_controller();
+        ms.tui = thread::spawn(|| thread::sleep(Duration::from_secs(5)));
+        while !ms.game_world.is_finished() {
+            thread::yield_now();
+        }
+
+        assert!(matches!(ms.check_threads(), Err(Gremlin::ThreadStopped("gameworld"))));
+        ms.fail(Gremlin::ThreadStopped("gameworld"));
+        assert_eq!(ms.shutdown.as_deref(), Some("The controller stopped: the gameworld thread stopped"));
+    }
+}
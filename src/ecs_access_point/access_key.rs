@@ -0,0 +1,97 @@
+//Jerome M. St.Martin
+//June 18, 2022
+
+//-----------------------------------------------------------------------------
+//------------- Keys into the ECSAccessPoint's Accessors, derived -------------
+//------------------- from the Component or Resource type ---------------------
+//-----------------------------------------------------------------------------
+
+use std::any::type_name;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use specs::{
+    prelude::Resource,
+    shred::ResourceId,
+    storage::MaskedStorage,
+    Component,
+};
+
+const UNNAMED: &str = "(unnamed)";
+
+///Identifies one lock in the ECSAccessPoint.
+///Keyed by the ResourceId (TypeId) of whatever specs actually fetches from the World:
+///the Resource itself, or the MaskedStorage<T> behind a Component's storage.
+///This is also what specs SystemData declares in its reads() and writes().
+///The name is for humans only, and takes no part in comparisons.
+#[derive(Clone)]
+pub struct AccessKey {
+    id: ResourceId,
+    name: &'static str,
+}
+
+impl AccessKey {
+    pub fn of_component<T: Component>() -> Self {
+        AccessKey {
+            id: ResourceId::new::<MaskedStorage<T>>(),
+            name: type_name::<T>(),
+        }
+    }
+
+    pub fn of_resource<T: Resource>() -> Self {
+        AccessKey {
+            id: ResourceId::new::<T>(),
+            name: type_name::<T>(),
+        }
+    }
+
+    ///For keys only known through a specs SystemData's reads() and writes(),
+    ///which give no name. The Accessor takes one from the first typed key for
+    ///the same thing, whether that came first or later.
+    pub fn of_resource_id(id: ResourceId) -> Self {
+        AccessKey { id, name: UNNAMED }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub(super) fn is_named(&self) -> bool {
+        self.name != UNNAMED
+    }
+}
+
+impl PartialEq for AccessKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for AccessKey {}
+
+impl Hash for AccessKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+//Ord is implemented so that multi-key acquisition always locks in the same order,
+//regardless of the order in which the caller listed the keys.
+impl PartialOrd for AccessKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AccessKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl fmt::Debug for AccessKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AccessKey({})", self.name)
+    }
+}
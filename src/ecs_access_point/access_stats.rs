@@ -0,0 +1,74 @@
+//Jerome M. St.Martin
+//June 21, 2022
+
+//-----------------------------------------------------------------------------
+//------------------ Lock Contention Metrics for each AccessKey ---------------
+//-----------------------------------------------------------------------------
+
+use std::io::Write;
+use std::time::Duration;
+
+use super::{AccessKey, AccessMode};
+
+///Counters for one mode (Read or Write) of one AccessKey.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ModeStats {
+    pub acquisitions: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+    pub total_hold: Duration,
+}
+
+///Counters for one AccessKey, split by Read and Write access.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct AccessStats {
+    pub read: ModeStats,
+    pub write: ModeStats,
+}
+
+impl AccessStats {
+    pub(super) fn record_acquisition(&mut self, mode: AccessMode, waited: Duration) {
+        let stats = self.mode_mut(mode);
+        stats.acquisitions += 1;
+        stats.total_wait += waited;
+        stats.max_wait = stats.max_wait.max(waited);
+    }
+
+    pub(super) fn record_release(&mut self, mode: AccessMode, held: Duration) {
+        self.mode_mut(mode).total_hold += held;
+    }
+
+    fn mode_mut(&mut self, mode: AccessMode) -> &mut ModeStats {
+        match mode {
+            AccessMode::Read => &mut self.read,
+            AccessMode::Write => &mut self.write,
+        }
+    }
+}
+
+///Writes one line per key and mode, as a plain-text table.
+pub fn write_report<W: Write>(out: &mut W, stats: &[(AccessKey, AccessStats)]) -> std::io::Result<()> {
+    writeln!(out, "ECSAccessPoint Lock Contention Report")?;
+    writeln!(
+        out,
+        "{:<60} {:<5} {:>12} {:>14} {:>14} {:>14}",
+        "key", "mode", "acquisitions", "total wait", "max wait", "total hold"
+    )?;
+
+    for (key, key_stats) in stats {
+        for (mode, mode_stats) in [("read", &key_stats.read), ("write", &key_stats.write)] {
+            writeln!(
+                out,
+                "{:<60} {:<5} {:>12} {:>14} {:>14} {:>14}",
+                key.name(),
+                mode,
+                mode_stats.acquisitions,
+                format!("{:?}", mode_stats.total_wait),
+                format!("{:?}", mode_stats.max_wait),
+                format!("{:?}", mode_stats.total_hold),
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+//Jerome M. St.Martin
+//June 24, 2022
+
+//-----------------------------------------------------------------------------
+//--------------- Turns Flagged Storage Events into Notifications -------------
+//------------------------------- for the View --------------------------------
+//-----------------------------------------------------------------------------
+
+/* A component whose storage is a FlaggedStorage can be tracked with
+ * ecs_ap.track::<T>(). From then on, every insert, removal, and mutable access
+ * is recorded by specs as a ComponentEvent, and ecs_ap.collect_deltas() turns
+ * everything recorded since the last call into DeltaNotifications.
+ *
+ * specs only records which Index changed, not what it changed from, so each
+ * tracker keeps its own copy of every tracked component as last seen, along with
+ * its Entity, since a deleted entity's Index alone no longer leads back to it.
+ * That copy is also what lets a mutable access that changed nothing be ignored.
+ *
+ * Changes are reported as they stand when collected, not one by one: a component
+ * moved twice since the last collection is reported as one move, and one added
+ * and then moved is only reported as added, where it ended up.
+ */
+
+use std::collections::HashMap;
+
+use specs::{
+    storage::{ComponentEvent, Tracked},
+    world::{EntitiesRes, Index},
+    Component, Entity, ReaderId,
+};
+
+use super::{AccessSet, ECSAccessPoint};
+use crate::common::DeltaNotification;
+
+///A component which can be passed to ECSAccessPoint::track().
+///Its storage must be a FlaggedStorage, i.e. Self::Storage: Tracked.
+pub trait TrackedComponent: Component + Clone + PartialEq + Send {
+    ///Used in ComponentAdded and ComponentRemoved notifications.
+    const NAME: &'static str;
+
+    ///The notification for an actual change to an existing component, if any.
+    fn changed(_entity: Entity, _old: &Self, _new: &Self) -> Option<DeltaNotification> {
+        None
+    }
+}
+
+//One per tracked component type, behind a trait object so that
+//the ECSAccessPoint can keep them all in one Vec.
+pub(super) trait ChangeCollector: Send {
+    fn collect(&mut self, ecs_ap: &ECSAccessPoint, deltas: &mut Vec<DeltaNotification>);
+}
+
+pub(super) struct ComponentTracker<T: TrackedComponent> {
+    reader: ReaderId<ComponentEvent>,
+    last_seen: HashMap<Index, (Entity, T)>,
+}
+
+impl<T: TrackedComponent> ComponentTracker<T> {
+    ///Components which already exist are seen as they are, and not reported as added.
+    pub(super) fn new(reader: ReaderId<ComponentEvent>, existing: HashMap<Index, (Entity, T)>) -> Self {
+        ComponentTracker {
+            reader,
+            last_seen: existing,
+        }
+    }
+}
+
+impl<T> ChangeCollector for ComponentTracker<T>
+where
+    T: TrackedComponent,
+    T::Storage: Tracked,
+{
+    fn collect(&mut self, ecs_ap: &ECSAccessPoint, deltas: &mut Vec<DeltaNotification>) {
+        let access = ecs_ap.req_access_set(AccessSet::new().read::<T>().read_resource::<EntitiesRes>());
+        let entities = access.read_resource::<EntitiesRes>();
+        let storage = access.read_storage::<T>();
+
+        for event in storage.channel().read(&mut self.reader) {
+            match *event {
+                //A component may already be gone again by the time its events are read,
+                //in which case its Removed event follows and there is nothing to report.
+                ComponentEvent::Inserted(idx) | ComponentEvent::Modified(idx) => {
+                    let entity = entities.entity(idx);
+                    let current = match storage.get(entity) {
+                        Some(current) => current.clone(),
+                        None => continue,
+                    };
+
+                    match self.last_seen.insert(idx, (entity, current.clone())) {
+                        None => deltas.push(DeltaNotification::ComponentAdded {
+                            entity,
+                            component: T::NAME,
+                        }),
+                        Some((_, old)) if old != current => deltas.extend(T::changed(entity, &old, &current)),
+                        Some(_) => {} //Mutably accessed, but nothing changed.
+                    }
+                }
+
+                ComponentEvent::Removed(idx) => {
+                    if let Some((entity, _)) = self.last_seen.remove(&idx) {
+                        deltas.push(DeltaNotification::ComponentRemoved {
+                            entity,
+                            component: T::NAME,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//Jerome M. St.Martin
+//June 20, 2022
+
+//-----------------------------------------------------------------------------
+//------------------ Debug-Build Deadlock Detection for the -------------------
+//--------------------------- ECSAccessPoint Locks ----------------------------
+//-----------------------------------------------------------------------------
+
+/* Every Accessor reports to one shared DeadlockDetector which thread holds which
+ * key, and which thread is about to block on which key. Before a thread blocks,
+ * the wait-for graph is walked from that thread; if the walk leads back to it,
+ * blocking would never end, so a report is returned instead.
+ *
+ * All calls are made while holding the reporting Accessor's state Mutex, so the
+ * graph can never disagree with the Accessors about who holds what.
+ * Only does anything in debug builds; in release builds every call returns at once.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::panic::Location;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+use super::{AccessKey, AccessMode};
+
+const ENABLED: bool = cfg!(debug_assertions);
+
+#[derive(Clone, Debug)]
+struct Hold {
+    key: AccessKey,
+    mode: AccessMode,
+    thread: ThreadId,
+    thread_name: String,
+    location: &'static Location<'static>,
+}
+
+#[derive(Clone, Debug)]
+struct Wait {
+    key: AccessKey,
+    mode: AccessMode,
+    thread_name: String,
+    location: &'static Location<'static>,
+}
+
+#[derive(Default)]
+struct LockGraph {
+    next_hold_id: u64,
+    holds: HashMap<u64, Hold>,
+    //Only waits without a deadline; a timed wait always ends, so it cannot deadlock.
+    waits: HashMap<ThreadId, Wait>,
+}
+
+#[derive(Default)]
+pub(super) struct DeadlockDetector {
+    graph: Mutex<LockGraph>,
+}
+
+impl DeadlockDetector {
+    pub(super) fn new() -> Self {
+        DeadlockDetector::default()
+    }
+
+    ///Records that the current thread is about to block on the key, with no deadline.
+    ///Returns Err(report) instead if doing so would deadlock.
+    pub(super) fn start_waiting(
+        &self,
+        key: &AccessKey,
+        mode: AccessMode,
+        location: &'static Location<'static>,
+    ) -> Result<(), String> {
+        if !ENABLED {
+            return Ok(());
+        }
+
+        let mut graph = self.graph();
+        let current = thread::current().id();
+
+        graph.waits.insert(
+            current,
+            Wait {
+                key: key.clone(),
+                mode,
+                thread_name: current_thread_name(),
+                location,
+            },
+        );
+
+        let mut path: Vec<ThreadId> = vec![current];
+        if graph.leads_back_to(current, &mut path) {
+            let report = graph.report(&path);
+            graph.waits.remove(&current);
+            return Err(report);
+        }
+
+        Ok(())
+    }
+
+    ///Records that the current thread is no longer waiting.
+    pub(super) fn stop_waiting(&self) {
+        if !ENABLED {
+            return;
+        }
+
+        self.graph().waits.remove(&thread::current().id());
+    }
+
+    ///Records that the current thread now holds the key. Returns an id for released().
+    pub(super) fn acquired(
+        &self,
+        key: &AccessKey,
+        mode: AccessMode,
+        location: &'static Location<'static>,
+    ) -> u64 {
+        if !ENABLED {
+            return 0;
+        }
+
+        let mut graph = self.graph();
+        let current = thread::current().id();
+
+        let hold_id = graph.next_hold_id;
+        graph.next_hold_id += 1;
+        graph.holds.insert(
+            hold_id,
+            Hold {
+                key: key.clone(),
+                mode,
+                thread: current,
+                thread_name: current_thread_name(),
+                location,
+            },
+        );
+
+        hold_id
+    }
+
+    pub(super) fn released(&self, hold_id: u64) {
+        if !ENABLED {
+            return;
+        }
+
+        self.graph().holds.remove(&hold_id);
+    }
+
+    ///Returns Err(report) if the current thread holds any key, e.g. before it
+    ///waits for every other guard to be dropped, which would then never happen.
+    pub(super) fn holds_nothing(&self, location: &'static Location<'static>) -> Result<(), String> {
+        if !ENABLED {
+            return Ok(());
+        }
+
+        let graph = self.graph();
+        let current = thread::current().id();
+
+        let mut report = String::new();
+        for hold in graph.holds.values().filter(|hold| hold.thread == current) {
+            let _ = writeln!(
+                report,
+                "    it holds {:?} access to {}, taken at {}",
+                hold.mode,
+                hold.key.name(),
+                hold.location,
+            );
+        }
+
+        if report.is_empty() {
+            return Ok(());
+        }
+
+        Err(format!(
+            "Deadlock detected by ECSAccessPoint!\n  thread '{}' waits for every guard to be dropped at {}\n{}",
+            current_thread_name(),
+            location,
+            report,
+        ))
+    }
+
+    //The graph is only ever left consistent, so poisoning is ignored.
+    fn graph(&self) -> std::sync::MutexGuard<'_, LockGraph> {
+        self.graph.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl LockGraph {
+    //The threads keeping a waiting thread blocked: a writer waits for everyone
+    //holding the key, a reader waits for the writer holding it and for any
+    //writers waiting on it, since those go first.
+    fn blockers_of(&self, waiter: ThreadId) -> Vec<ThreadId> {
+        let wait = match self.waits.get(&waiter) {
+            Some(wait) => wait,
+            None => return Vec::new(), //Not blocked, so the chain ends here.
+        };
+
+        let mut blockers: HashSet<ThreadId> = self
+            .holds
+            .values()
+            .filter(|hold| hold.key == wait.key)
+            .filter(|hold| wait.mode == AccessMode::Write || hold.mode == AccessMode::Write)
+            .map(|hold| hold.thread)
+            .collect();
+
+        if wait.mode == AccessMode::Read {
+            blockers.extend(
+                self.waits
+                    .iter()
+                    .filter(|(thread, other)| **thread != waiter && other.key == wait.key)
+                    .filter(|(_, other)| other.mode == AccessMode::Write)
+                    .map(|(thread, _)| *thread),
+            );
+        }
+
+        blockers.into_iter().collect()
+    }
+
+    //Depth-first walk of the wait-for graph. On success, path holds the cycle.
+    fn leads_back_to(&self, target: ThreadId, path: &mut Vec<ThreadId>) -> bool {
+        let from = *path.last().expect("path always starts with the waiting thread");
+
+        for blocker in self.blockers_of(from) {
+            if blocker == target {
+                return true;
+            }
+
+            if path.contains(&blocker) {
+                continue;
+            }
+
+            path.push(blocker);
+            if self.leads_back_to(target, path) {
+                return true;
+            }
+            path.pop();
+        }
+
+        false
+    }
+
+    fn report(&self, cycle: &[ThreadId]) -> String {
+        let mut report = String::from("Deadlock detected by ECSAccessPoint!\n");
+
+        for (idx, thread) in cycle.iter().enumerate() {
+            let wait = &self.waits[thread];
+            let next = cycle[(idx + 1) % cycle.len()];
+
+            let _ = writeln!(
+                report,
+                "  thread '{}' waits for {:?} access to {} at {}",
+                wait.thread_name,
+                wait.mode,
+                wait.key.name(),
+                wait.location,
+            );
+
+            for hold in self.holds.values().filter(|hold| hold.thread == next) {
+                let _ = writeln!(
+                    report,
+                    "    thread '{}' holds {:?} access to {}, taken at {}",
+                    hold.thread_name,
+                    hold.mode,
+                    hold.key.name(),
+                    hold.location,
+                );
+            }
+
+            if let Some(next_wait) = self.waits.get(&next) {
+                if next_wait.key == wait.key && next_wait.mode == AccessMode::Write {
+                    let _ = writeln!(
+                        report,
+                        "    thread '{}' is a writer queued ahead of it",
+                        next_wait.thread_name,
+                    );
+                }
+            }
+        }
+
+        report
+    }
+}
+
+fn current_thread_name() -> String {
+    let current = thread::current();
+    match current.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", current.id()),
+    }
+}
+
+///Gives the terminal back to the user before panicking,
+///so that the report is actually readable. The panic hook would too,
+///but tests, and anything else not run from main(), do not install it.
+pub(super) fn restore_terminal_and_panic(report: String) -> ! {
+    crate::terminal::restore();
+    panic!("{}", report);
+}
@@ -0,0 +1,50 @@
+//Jerome M. St.Martin
+//June 22, 2022
+
+//-----------------------------------------------------------------------------
+//------------------ Builds Entities through ECSAccessPoint -------------------
+//-----------------------------------------------------------------------------
+
+use specs::{Component, Entity};
+
+use super::ECSAccessPoint;
+
+///Returned by ECSAccessPoint::create_entity(). The entity already exists;
+///each with() locks and inserts into one storage at a time.
+pub struct EntityBuilder<'a> {
+    ecs_ap: &'a ECSAccessPoint,
+    entity: Entity,
+    built: bool,
+}
+
+impl<'a> EntityBuilder<'a> {
+    pub(super) fn new(ecs_ap: &'a ECSAccessPoint, entity: Entity) -> Self {
+        EntityBuilder {
+            ecs_ap,
+            entity,
+            built: false,
+        }
+    }
+
+    #[track_caller]
+    pub fn with<T: Component>(self, c: T) -> Self {
+        //Nobody else knows of this entity yet, so it cannot have been deleted.
+        self.ecs_ap
+            .insert_component(c, self.entity)
+            .expect("EntityBuilder's entity found to be dead during with()");
+        self
+    }
+
+    pub fn build(mut self) -> Entity {
+        self.built = true;
+        self.entity
+    }
+}
+
+impl<'a> Drop for EntityBuilder<'a> {
+    fn drop(&mut self) {
+        if !self.built {
+            let _ = self.ecs_ap.delete_entity(self.entity);
+        }
+    }
+}
@@ -7,137 +7,620 @@
 //----------------------------- to ECS Storages -------------------------------
 //-----------------------------------------------------------------------------
 
-use std::collections::HashMap;
-use std::sync::{Arc, Condvar, Mutex};
+use std::cell::UnsafeCell;
+use std::collections::{BTreeMap, HashMap};
+use std::panic::Location;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use specs::{Component, Entity, World};
+use specs::{
+    prelude::{Resource, RunNow, System},
+    shred::Accessor as SystemAccessor,
+    storage::Tracked,
+    world::{EntitiesRes, Index},
+    Component, Entity, Join, World, WorldExt,
+};
 
+mod access_key;
+mod access_stats;
+mod change_tracker;
+mod deadlock_detector;
+mod entity_builder;
+mod multi_access_guard;
 mod storage_access_guard;
 mod resource_access_guard;
+mod world_gate;
 
-pub use storage_access_guard::StorageAccessGuard;
-pub use resource_access_guard::ResourceAccessGuard;
+pub use access_key::AccessKey;
+pub use access_stats::AccessStats;
+pub use change_tracker::TrackedComponent;
+pub use entity_builder::EntityBuilder;
+pub use multi_access_guard::{AccessMode, AccessSet, MultiAccessGuard};
+pub use storage_access_guard::{ReadGuard, WriteGuard};
+pub use resource_access_guard::{FetchGuard, FetchMutGuard};
 
-//FOR TESTING ----------
-use crate::gameworld::resources::map::Map;
-use crate::common::Coords;
-//----------------------    
+use change_tracker::{ChangeCollector, ComponentTracker};
+use deadlock_detector::DeadlockDetector;
+use storage_access_guard::StorageAccessGuard;
+use resource_access_guard::ResourceAccessGuard;
+use world_gate::{GatePass, WorldGate};
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
-pub enum AccessKey { //add variants representing each Component or Resource as needed
-    //Resources
-    Map,
-    Player,
-
-    //Components
-    Hostile,
-    Position,
-}
+use crate::common::DeltaNotification;
+use crate::error::Gremlin;
 
 pub struct ECSAccessPoint {
     accessors: Mutex<HashMap<AccessKey, Arc<Accessor>>>,
-    ecs: World,
+    detector: Arc<DeadlockDetector>, //Shared by every Accessor; no-op in release builds.
+    gate: Arc<WorldGate>,            //Kept empty and closed while maintain() runs.
+    trackers: Mutex<Vec<Box<dyn ChangeCollector>>>, //One per track()ed component type.
+    ecs: UnsafeCell<World>,          //Only ever reached through world() or maintain().
 }
 
+//SAFETY: World itself is Sync. The only &mut World ever made is in maintain(),
+//which first waits until no GatePass, and therefore no &World, is alive,
+//and keeps new ones from being made until it is done.
+unsafe impl Sync for ECSAccessPoint {}
+
 impl ECSAccessPoint {
     pub fn new(ecs: specs::World) -> Self {
         ECSAccessPoint {
             accessors: Mutex::new(HashMap::new()),
-            ecs,
+            detector: Arc::new(DeadlockDetector::new()),
+            gate: Arc::new(WorldGate::new()),
+            trackers: Mutex::new(Vec::new()),
+            ecs: UnsafeCell::new(ecs),
         }
     }
 
-    //FOR TESTING ONLY
-    fn helper(map: &Map) {
-        let mut idx = 0;
-        for _ in 0..map.size {
-            print!("\n\r");
-            for _ in 0..map.size {
-                let glyph: char;
-                if map.walls[idx as usize] { glyph = '#'; } else { glyph = '.'; };
-                print!("{}", glyph);
-                idx += 1;
-            }
+    #[track_caller]
+    pub fn insert_component<T: Component>(
+        &self,
+        c: T,
+        e: Entity,
+    ) -> Result<Option<T>, specs::error::Error> {
+        self.write::<T>().insert(e, c)
+    }
+
+    ///Creates an entity at once; components are added through the returned builder.
+    ///Like specs' own EntityBuilder, dropping it without calling build() deletes the entity.
+    #[track_caller]
+    pub fn create_entity(&self) -> EntityBuilder<'_> {
+        let entity = self.read_resource::<EntitiesRes>().create();
+        EntityBuilder::new(self, entity)
+    }
+
+    ///Marks the entity for deletion. It stays alive, with all of its components,
+    ///until the next maintain().
+    #[track_caller]
+    pub fn delete_entity(&self, e: Entity) -> Result<(), Gremlin> {
+        self.read_resource::<EntitiesRes>()
+            .delete(e)
+            .map_err(|wrong_gen| Gremlin::SpecsErr(specs::error::Error::WrongGeneration(wrong_gen)))
+    }
+
+    #[track_caller]
+    pub fn is_alive(&self, e: Entity) -> bool {
+        self.read_resource::<EntitiesRes>().is_alive(e)
+    }
+
+    ///Runs World::maintain(), which needs the whole World to itself:
+    ///blocks until every guard on every thread has been dropped.
+    ///Must never be called while the calling thread still holds a guard.
+    #[track_caller]
+    pub fn maintain(&self) {
+        if let Err(report) = self.detector.holds_nothing(Location::caller()) {
+            deadlock_detector::restore_terminal_and_panic(report);
+        }
+
+        let _maintenance = self.gate.open_maintenance();
+
+        //SAFETY: the gate is empty and closed, so nothing else can be using the World.
+        let ecs = unsafe { &mut *self.ecs.get() };
+        ecs.maintain();
+    }
+
+    ///From now on, changes to T are turned into DeltaNotifications by collect_deltas().
+    #[track_caller]
+    pub fn track<T>(&self)
+    where
+        T: TrackedComponent,
+        T::Storage: Tracked,
+    {
+        let (reader, existing) = {
+            let mut storage = self.write::<T>();
+            let entities = self.read_resource::<EntitiesRes>();
+            let existing: HashMap<Index, (Entity, T)> = (&*entities, &*storage)
+                .join()
+                .map(|(entity, c)| (entity.id(), (entity, c.clone())))
+                .collect();
+
+            (storage.register_reader(), existing)
+        };
+
+        self.trackers
+            .lock()
+            .expect("Mutex found to be poisoned during ecs_ap.track()")
+            .push(Box::new(ComponentTracker::<T>::new(reader, existing)));
+    }
+
+    ///Every change to a track()ed component since the last call, grouped by component type,
+    ///oldest first within each. Components removed by maintain() are included,
+    ///so this is best called right after it.
+    #[track_caller]
+    pub fn collect_deltas(&self) -> Vec<DeltaNotification> {
+        let mut trackers = self
+            .trackers
+            .lock()
+            .expect("Mutex found to be poisoned during ecs_ap.collect_deltas()");
+
+        let mut deltas: Vec<DeltaNotification> = Vec::new();
+        for tracker in trackers.iter_mut() {
+            tracker.collect(self, &mut deltas);
         }
-        print!("\n\r");
+
+        deltas
     }
 
-    //FOR TESTING ONLY
-    pub fn print_map(&self) {
-        let access_guard = self.req_access(AccessKey::Map);
-        let map = access_guard.read_resource::<Map>(&self.ecs);
+    //The lock is always picked by the type being fetched,
+    //so it is impossible to fetch something under another thing's lock.
+    //Blocking access panics if a writer panicked while holding the same lock;
+    //use the try_* or *_timeout variants to get a Gremlin instead.
+    //Nothing outside the tests reads one lone storage at the moment; take
+    //a storage alongside anything else through req_access_set().
+    #[allow(dead_code)]
+    #[track_caller]
+    pub fn read<T: Component>(&self) -> ReadGuard<'_, T> {
+        Self::or_panic(self.read_until::<T>(None))
+    }
 
-        Self::helper(&map);
+    #[track_caller]
+    pub fn write<T: Component>(&self) -> WriteGuard<'_, T> {
+        Self::or_panic(self.write_until::<T>(None))
+    }
 
-        for i in 0..map.size {
-            print!("\n\r");
-            for j in 0..map.size {
-                if let Ok(wall_glyph) = map.prettify_wall(&map.walls, Coords::new(j, i)) {
-                    print!("{}", wall_glyph);
-                } else {
-                    print!(".");
-                }
+    #[track_caller]
+    pub fn read_resource<T: Resource>(&self) -> FetchGuard<'_, T> {
+        Self::or_panic(self.read_resource_until::<T>(None))
+    }
+
+    #[track_caller]
+    pub fn write_resource<T: Resource>(&self) -> FetchMutGuard<'_, T> {
+        Self::or_panic(self.write_resource_until::<T>(None))
+    }
+
+    ///Acquires every key in the AccessSet before returning, always in AccessKey order,
+    ///so that two threads asking for overlapping sets can never deadlock each other.
+    ///A key requested for both reading and writing is acquired for writing.
+    #[track_caller]
+    pub fn req_access_set(&self, set: AccessSet) -> MultiAccessGuard<'_> {
+        Self::or_panic(self.req_access_set_until(set, None))
+    }
+
+    //Non-blocking: Err(Gremlin::AccessTimeout) if access cannot be granted right now.
+    //Only the tests need these, and most of the timed ones, so far.
+    #[cfg(test)]
+    #[track_caller]
+    pub fn try_read<T: Component>(&self) -> Result<ReadGuard<'_, T>, Gremlin> {
+        self.read_until::<T>(Some(Instant::now()))
+    }
+
+    #[cfg(test)]
+    #[track_caller]
+    pub fn try_write<T: Component>(&self) -> Result<WriteGuard<'_, T>, Gremlin> {
+        self.write_until::<T>(Some(Instant::now()))
+    }
+
+    #[cfg(test)]
+    #[track_caller]
+    pub fn try_read_resource<T: Resource>(&self) -> Result<FetchGuard<'_, T>, Gremlin> {
+        self.read_resource_until::<T>(Some(Instant::now()))
+    }
+
+    #[cfg(test)]
+    #[track_caller]
+    pub fn try_write_resource<T: Resource>(&self) -> Result<FetchMutGuard<'_, T>, Gremlin> {
+        self.write_resource_until::<T>(Some(Instant::now()))
+    }
+
+    #[cfg(test)]
+    #[track_caller]
+    pub fn try_req_access_set(&self, set: AccessSet) -> Result<MultiAccessGuard<'_>, Gremlin> {
+        self.req_access_set_until(set, Some(Instant::now()))
+    }
+
+    //Timed: Err(Gremlin::AccessTimeout) if access is not granted within the timeout.
+    //For a set, the timeout covers acquiring the whole set.
+    #[cfg(test)]
+    #[track_caller]
+    pub fn read_timeout<T: Component>(&self, timeout: Duration) -> Result<ReadGuard<'_, T>, Gremlin> {
+        self.read_until::<T>(Some(Instant::now() + timeout))
+    }
+
+    #[cfg(test)]
+    #[track_caller]
+    pub fn write_timeout<T: Component>(&self, timeout: Duration) -> Result<WriteGuard<'_, T>, Gremlin> {
+        self.write_until::<T>(Some(Instant::now() + timeout))
+    }
+
+    #[cfg(test)]
+    #[track_caller]
+    pub fn read_resource_timeout<T: Resource>(&self, timeout: Duration) -> Result<FetchGuard<'_, T>, Gremlin> {
+        self.read_resource_until::<T>(Some(Instant::now() + timeout))
+    }
+
+    #[track_caller]
+    pub fn write_resource_timeout<T: Resource>(&self, timeout: Duration) -> Result<FetchMutGuard<'_, T>, Gremlin> {
+        self.write_resource_until::<T>(Some(Instant::now() + timeout))
+    }
+
+    #[cfg(test)]
+    #[track_caller]
+    pub fn req_access_set_timeout(&self, set: AccessSet, timeout: Duration) -> Result<MultiAccessGuard<'_>, Gremlin> {
+        self.req_access_set_until(set, Some(Instant::now() + timeout))
+    }
+
+    ///A snapshot of every key's contention counters so far, sorted by key name.
+    pub fn access_stats(&self) -> Vec<(AccessKey, AccessStats)> {
+        let accessors = self
+            .accessors
+            .lock()
+            .expect("Mutex found to be poisoned during ecs_ap.access_stats()");
+
+        let mut stats: Vec<(AccessKey, AccessStats)> = accessors
+            .values()
+            .map(|accessor| (accessor.key(), accessor.state().stats))
+            .collect();
+
+        stats.sort_by_key(|(key, _)| key.name());
+        stats
+    }
+
+    ///Writes access_stats() to a plain-text report file, replacing any previous one.
+    pub fn write_access_report<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), Gremlin> {
+        let mut file = std::fs::File::create(path)?;
+        access_stats::write_report(&mut file, &self.access_stats())?;
+        Ok(())
+    }
+
+    ///Recovers a lock poisoned by a writer that panicked, once the caller has dealt with
+    ///whatever that writer may have left half-done. Does nothing if the lock is not poisoned.
+    ///AccessPoisoned is fatal to the game, so only the tests recover from it.
+    #[cfg(test)]
+    pub fn clear_poison(&self, key: &AccessKey) {
+        let accessors = self
+            .accessors
+            .lock()
+            .expect("Mutex found to be poisoned during ecs_ap.clear_poison()");
+
+        if let Some(accessor) = accessors.get(key) {
+            accessor.state().poisoned = false;
+        }
+    }
+
+    ///Runs a specs System under exactly the locks its SystemData declares
+    ///in reads() and writes(), all acquired at once through req_access_set().
+    ///Example: ecs_ap.run_system(&mut RenderingSystem {});
+    #[track_caller]
+    pub fn run_system<'a, S: System<'a>>(&'a self, system: &mut S) {
+        let set = {
+            let accessor = system.accessor();
+            AccessSet::from_resource_ids(accessor.reads(), accessor.writes())
+        };
+
+        let _guard = self.req_access_set(set);
+        system.run_now(self.world());
+    }
+
+    #[track_caller]
+    fn read_until<T: Component>(&self, deadline: Option<Instant>) -> Result<ReadGuard<'_, T>, Gremlin> {
+        self.req_access(AccessKey::of_component::<T>())
+            .read_storage::<T>(self.world(), deadline)
+    }
+
+    #[track_caller]
+    fn write_until<T: Component>(&self, deadline: Option<Instant>) -> Result<WriteGuard<'_, T>, Gremlin> {
+        self.req_access(AccessKey::of_component::<T>())
+            .write_storage::<T>(self.world(), deadline)
+    }
+
+    #[track_caller]
+    fn read_resource_until<T: Resource>(&self, deadline: Option<Instant>) -> Result<FetchGuard<'_, T>, Gremlin> {
+        self.req_access(AccessKey::of_resource::<T>())
+            .read_resource::<T>(self.world(), deadline)
+    }
+
+    #[track_caller]
+    fn write_resource_until<T: Resource>(&self, deadline: Option<Instant>) -> Result<FetchMutGuard<'_, T>, Gremlin> {
+        self.req_access(AccessKey::of_resource::<T>())
+            .write_resource::<T>(self.world(), deadline)
+    }
+
+    //If any key cannot be acquired, the ones already acquired are released on return.
+    #[track_caller]
+    fn req_access_set_until(&self, set: AccessSet, deadline: Option<Instant>) -> Result<MultiAccessGuard<'_>, Gremlin> {
+        //Taken even for an empty set, since the guard hands out the World regardless.
+        let pass = GatePass::new(self.gate.clone());
+        let mut ordered: BTreeMap<AccessKey, AccessMode> = BTreeMap::new();
+
+        for key in set.reads {
+            ordered.entry(key).or_insert(AccessMode::Read);
+        }
+
+        for key in set.writes {
+            ordered.insert(key, AccessMode::Write);
+        }
+
+        let mut held: Vec<(AccessKey, AccessMode, AccessGuard)> = Vec::with_capacity(ordered.len());
+        for (key, mode) in ordered {
+            let mut guard = self.req_access(key);
+            match mode {
+                AccessMode::Read => guard.lock_read(deadline)?,
+                AccessMode::Write => guard.lock_write(deadline)?,
             }
+            //The Accessor's copy of the key is used, since it may carry a better name.
+            held.push((guard.key(), mode, guard));
         }
-        print!("\n\r");
+
+        Ok(MultiAccessGuard::new(self.world(), held, pass))
     }
 
-    pub fn insert_component<T: Component>(
-        &self,
-        key: AccessKey,
-        c: T,
-        e: Entity,
-    ) -> Result<Option<T>, specs::error::Error> {
-        self.req_access(key)
-            .write_storage::<T>(&self.ecs)
-            .insert(e, c)
+    #[track_caller]
+    fn or_panic<G>(access: Result<G, Gremlin>) -> G {
+        access.unwrap_or_else(|e| panic!("ECSAccessPoint access failed: {}", e))
+    }
+
+    //SAFETY: must only be called while the calling thread holds a GatePass,
+    //i.e. an AccessGuard or MultiAccessGuard, and the returned reference
+    //must not outlive it. Every guard is handed the World this way.
+    fn world(&self) -> &World {
+        unsafe { &*self.ecs.get() }
     }
 
     fn req_access(&self, key: AccessKey) -> AccessGuard {
+        //Entered before the Accessors' Mutex is taken, since this may block on a maintain().
+        let pass = GatePass::new(self.gate.clone());
+
         let mut accessors = self
             .accessors
             .lock()
             .expect("Mutex found to be poisoned during ecs_ap.req_access()");
         
-        let accessor_arc = accessors.entry(key) //If AccessGuard found, skip next line
-            .or_insert(Arc::new(Accessor::new())) //else insert new AccessGuard
+        let accessor_arc = accessors.entry(key.clone()) //If AccessGuard found, skip next line
+            .or_insert_with(|| Arc::new(Accessor::new(key.clone(), self.detector.clone()))) //else insert new AccessGuard
             .clone();
+        accessor_arc.learn_name(&key);
 
-        AccessGuard::new(accessor_arc)
+        AccessGuard::new(accessor_arc, pass)
     }
 }
 
 //These structs must be wrapped in a Mutex.
+//Writer-preferring: once a writer is waiting, no new readers are let in, so a
+//writer only ever waits for the readers that were already inside to leave.
+//This also means a thread must never re-acquire a key it is already reading from.
 #[derive(Debug)]
 struct AccessorState {
     pub readers: u8,
-    pub read_allowed: bool,
-    pub write_allowed: bool,
+    pub writer_active: bool,
+    pub writers_waiting: u8,
+    pub poisoned: bool, //Set when a thread panics while holding Write access.
+    pub stats: AccessStats,
 }
 
 pub struct Accessor {
+    key: Mutex<AccessKey>, //Only locked on its own, to read or fix up the name.
     mtx: Mutex<AccessorState>,
-    cvar: Condvar,
+    read_cvar: Condvar,  //Waited on by threads wanting Read access.
+    write_cvar: Condvar, //Waited on by threads wanting Write access.
+    detector: Arc<DeadlockDetector>,
+}
+
+enum WaitOutcome {
+    Granted,
+    TimedOut,
+    Deadlock(String), //Carries the DeadlockDetector's report.
 }
 
 impl Accessor {
-    pub(super) fn new() -> Self {
+    fn new(key: AccessKey, detector: Arc<DeadlockDetector>) -> Self {
         Accessor {
+            key: Mutex::new(key),
             mtx: Mutex::new(AccessorState {
                 readers: 0,
-                read_allowed: true,
-                write_allowed: true,
+                writer_active: false,
+                writers_waiting: 0,
+                poisoned: false,
+                stats: AccessStats::default(),
             }),
-            cvar: Condvar::new(),
+            read_cvar: Condvar::new(),
+            write_cvar: Condvar::new(),
+            detector,
+        }
+    }
+
+    fn key(&self) -> AccessKey {
+        self.key.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    //A key made by run_system() has no name, but a typed key for the same thing does.
+    fn learn_name(&self, key: &AccessKey) {
+        let mut own = self.key.lock().unwrap_or_else(PoisonError::into_inner);
+        if !own.is_named() && key.is_named() {
+            *own = key.clone();
+        }
+    }
+
+    //AccessorState is only ever touched in short critical sections which leave it
+    //consistent, so a poisoned std Mutex is simply recovered. Poisoning that
+    //matters, i.e. a panic mid-write to the ECS, is tracked in AccessorState.poisoned.
+    fn state(&self) -> MutexGuard<'_, AccessorState> {
+        self.mtx.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    //Waits on the condvar while blocked() holds, until the deadline if there is one.
+    //A wait with no deadline is first checked with the DeadlockDetector.
+    fn wait_while<'g, F>(
+        &self,
+        cvar: &Condvar,
+        mut state: MutexGuard<'g, AccessorState>,
+        mode: AccessMode,
+        deadline: Option<Instant>,
+        location: &'static Location<'static>,
+        mut blocked: F,
+    ) -> (MutexGuard<'g, AccessorState>, WaitOutcome)
+    where
+        F: FnMut(&mut AccessorState) -> bool,
+    {
+        if !blocked(&mut state) {
+            return (state, WaitOutcome::Granted);
+        }
+
+        match deadline {
+            None => {
+                if let Err(report) = self.detector.start_waiting(&self.key(), mode, location) {
+                    return (state, WaitOutcome::Deadlock(report));
+                }
+
+                let state = cvar
+                    .wait_while(state, blocked)
+                    .unwrap_or_else(PoisonError::into_inner);
+
+                self.detector.stop_waiting();
+                (state, WaitOutcome::Granted)
+            }
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                let (state, result) = cvar
+                    .wait_timeout_while(state, timeout, blocked)
+                    .unwrap_or_else(PoisonError::into_inner);
+
+                if result.timed_out() {
+                    (state, WaitOutcome::TimedOut)
+                } else {
+                    (state, WaitOutcome::Granted)
+                }
+            }
         }
     }
 }
 
-pub struct AccessGuard(Arc<Accessor>);
+pub struct AccessGuard {
+    accessor: Arc<Accessor>,
+    held: Option<AccessMode>, //None until a lock_*() call succeeds.
+    hold_id: u64,             //The DeadlockDetector's record of this hold.
+    acquired_at: Instant,     //For AccessStats hold times.
+    _pass: GatePass,          //Dropped after the lock is released, by Drop below.
+}
 
 impl AccessGuard {
-    pub(super) fn new(accessor: Arc<Accessor>) -> Self {
-        AccessGuard(accessor.clone())
+    fn new(accessor: Arc<Accessor>, pass: GatePass) -> Self {
+        AccessGuard {
+            accessor,
+            held: None,
+            hold_id: 0,
+            acquired_at: Instant::now(),
+            _pass: pass,
+        }
+    }
+
+    ///Blocks until non-exclusive Read access is granted, or until the deadline has passed.
+    ///No deadline means wait forever; a deadline of Instant::now() means do not wait at all.
+    #[track_caller]
+    pub(super) fn lock_read(&mut self, deadline: Option<Instant>) -> Result<(), Gremlin> {
+        let location = Location::caller();
+        let wait_started = Instant::now();
+
+        //While a writer holds access, or is waiting for it, wait until the calling thread is
+        //notified on the read condvar. Once notified, the calling thread is awoken,
+        //the lock for the mutex (mtx) is acquired, and execution of this function continues.
+        let (mut accessor_state, outcome) = self.wait_while(
+            &self.read_cvar,
+            self.state(),
+            AccessMode::Read,
+            deadline,
+            location,
+            |acc_state: &mut AccessorState| {
+                !acc_state.poisoned && (acc_state.writer_active || acc_state.writers_waiting > 0)
+            },
+        );
+
+        match outcome {
+            WaitOutcome::Deadlock(report) => {
+                drop(accessor_state);
+                deadlock_detector::restore_terminal_and_panic(report);
+            }
+            _ if accessor_state.poisoned => {
+                return Err(Gremlin::AccessPoisoned(self.key()));
+            }
+            WaitOutcome::TimedOut => {
+                return Err(Gremlin::AccessTimeout(self.key()));
+            }
+            WaitOutcome::Granted => {}
+        }
+
+        accessor_state.readers += 1;
+        accessor_state.stats.record_acquisition(AccessMode::Read, wait_started.elapsed());
+        let hold_id = self.detector.acquired(&self.key(), AccessMode::Read, location);
+        drop(accessor_state);
+
+        self.held = Some(AccessMode::Read);
+        self.hold_id = hold_id;
+        self.acquired_at = Instant::now();
+        Ok(())
+    }
+
+    ///Blocks until exclusive Write access is granted, or until the deadline has passed.
+    ///No deadline means wait forever; a deadline of Instant::now() means do not wait at all.
+    #[track_caller]
+    pub(super) fn lock_write(&mut self, deadline: Option<Instant>) -> Result<(), Gremlin> {
+        let location = Location::caller();
+        let wait_started = Instant::now();
+
+        //Registering as a waiting writer first is what stops new readers from getting in.
+        let mut accessor_state = self.state();
+        accessor_state.writers_waiting += 1;
+
+        /*While anyone else holds access, wait until the calling thread is notified on the
+         * write condvar. Once notified, the calling thread is awoken,
+         * the lock for the mutex is acquired, and the execution of this function continues.*/
+        let (mut accessor_state, outcome) = self.wait_while(
+            &self.write_cvar,
+            accessor_state,
+            AccessMode::Write,
+            deadline,
+            location,
+            |acc_state: &mut AccessorState| {
+                !acc_state.poisoned && (acc_state.writer_active || acc_state.readers > 0)
+            },
+        );
+
+        accessor_state.writers_waiting -= 1;
+
+        let poisoned = accessor_state.poisoned;
+        if poisoned || !matches!(outcome, WaitOutcome::Granted) {
+            //Giving up may have been the last thing keeping readers out.
+            if accessor_state.writers_waiting == 0 && !accessor_state.writer_active {
+                self.read_cvar.notify_all();
+            }
+            drop(accessor_state);
+
+            return match outcome {
+                WaitOutcome::Deadlock(report) => deadlock_detector::restore_terminal_and_panic(report),
+                _ if poisoned => Err(Gremlin::AccessPoisoned(self.key())),
+                _ => Err(Gremlin::AccessTimeout(self.key())),
+            };
+        }
+
+        accessor_state.writer_active = true;
+        accessor_state.stats.record_acquisition(AccessMode::Write, wait_started.elapsed());
+        let hold_id = self.detector.acquired(&self.key(), AccessMode::Write, location);
+        drop(accessor_state);
+
+        self.held = Some(AccessMode::Write);
+        self.hold_id = hold_id;
+        self.acquired_at = Instant::now();
+        Ok(())
     }
 }
 
@@ -145,56 +628,605 @@ impl std::ops::Deref for AccessGuard {
     type Target = Accessor;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.accessor
     }
 }
 
 impl Drop for AccessGuard {
     fn drop(&mut self) {
 
-        let mut access = self
-            .mtx
-            .lock()
-            .expect("AccessGuard Mutex poisoned before .drop()");
-
-        match (access.write_allowed, access.read_allowed) {
-            (false, false) => {
-                //This AccessGuard was giving exclusive Write access,
-                //so it is now safe to allow any type of access.
-                access.write_allowed = true;
-                access.read_allowed = true;
+        let held = match self.held {
+            Some(mode) => mode,
+            None => return, //Never acquired, e.g. timed out, so there is nothing to release.
+        };
+
+        let mut access = self.state();
+        self.detector.released(self.hold_id);
+        access.stats.record_release(held, self.acquired_at.elapsed());
+
+        match held {
+            AccessMode::Write => {
+                //This AccessGuard was giving exclusive Write access.
+                //If the writer panicked, whatever it was writing may be half-done.
+                access.writer_active = false;
+                if thread::panicking() {
+                    access.poisoned = true;
+                }
             },
 
-            (false, true) => {
-                //This AccessGuard was granding non-exclusive Read access,
+            AccessMode::Read => {
+                //This AccessGuard was granting non-exclusive Read access,
                 //so the reader count must be decremented.
-
-                //POSSIBLE WRITER-STARVATION PROBLEM, but I'm not sure it matters
-                //in this context? Like... all ECS work that will be done also must
-                //be done in-between each user input event. And every aspect of this
-                //program's multithreadedness is blocking... so doesn't that
-                //guarantee that write access will never be starved? I think yes.
                 access.readers -= 1;
+            },
+        }
+
+        //Poisoning must wake everyone, so that they can give up.
+        if access.poisoned {
+            self.write_cvar.notify_all();
+            self.read_cvar.notify_all();
+            return;
+        }
+
+        //Waiting writers go first. Only the last reader out can let a writer in,
+        //and only one writer can get in, so only one needs to be woken.
+        //Readers are only woken once no writer is left waiting.
+        if access.writers_waiting > 0 {
+            if access.readers == 0 {
+                self.write_cvar.notify_one();
+            }
+        } else {
+            self.read_cvar.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Barrier};
+    use std::thread;
+    use std::time::Duration;
+
+    use specs::{Builder, Join, ReadExpect, ReadStorage, WorldExt, WriteExpect, WriteStorage};
+
+    use super::*;
+    use crate::common::Coords;
+    use crate::gameworld::resources::map::Map;
+    use crate::gameworld::components::{self, Player, Position};
+    use crate::gameworld::resources;
+
+    fn test_ecs_ap() -> (Arc<ECSAccessPoint>, Entity) {
+        let mut ecs: specs::World = WorldExt::new();
+        resources::insert_all_resources(&mut ecs);
+        components::register_all_components(&mut ecs);
+
+        let entity = ecs
+            .create_entity()
+            .with(Position(Coords::new(1u16, 1u16)))
+            .build();
+
+        (Arc::new(ECSAccessPoint::new(ecs)), entity)
+    }
+
+    #[test]
+    fn test_access_set_write_wins() {
+        let (ecs_ap, entity) = test_ecs_ap();
+
+        let guard = ecs_ap.req_access_set(
+            AccessSet::new()
+                .read::<Position>()
+                .write::<Position>(),
+        );
+
+        let mut positions = guard.write_storage::<Position>();
+        positions.insert(entity, Position(Coords::new(2u16, 2u16))).unwrap();
+        drop(positions);
+
+        let positions = guard.read_storage::<Position>();
+        assert_eq!(positions.get(entity), Some(&Position(Coords::new(2u16, 2u16))));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_access_set_unheld_key() {
+        let (ecs_ap, _) = test_ecs_ap();
+
+        let guard = ecs_ap.req_access_set(AccessSet::new().read::<Position>());
+        let _ = guard.write_resource::<Map>();
+    }
+
+    #[test]
+    fn test_access_set_no_deadlock() {
+        const ITERATIONS: usize = 2_000;
+
+        let (ecs_ap, entity) = test_ecs_ap();
+        let gw_ecs_ap = ecs_ap.clone();
+        let tui_ecs_ap = ecs_ap.clone();
+        let (done_tx, done_rx) = mpsc::channel();
+        let gw_done_tx = done_tx.clone();
 
-                if access.readers == 0 {
-                    //Write access is allowed again, since there is no one with access currently.
-                    access.write_allowed = true;
+        //GameWorld: writes Position, reads Map. Lists Map first.
+        thread::spawn(move || {
+            for i in 0..ITERATIONS {
+                let guard = gw_ecs_ap.req_access_set(
+                    AccessSet::new()
+                        .read_resource::<Map>()
+                        .write::<Position>(),
+                );
 
-                    //Due to possible writer-starvation problem:
-                    //Here, if required, a thread awaiting WRITE access should be notified.
-                    //This behaviour requires a second Condvar in AccessGuard, specifically
-                    //used by threads waiting for Write access.
-                    //Seems unneccesary at this time, but I'll leave these notes for future me,
-                    //just in case I am very wrong, which has a definite non-zero probability.
+                let map = guard.read_resource::<Map>();
+                let mut positions = guard.write_storage::<Position>();
+                let x = (i as u16) % map.size;
+                positions.insert(entity, Position(Coords::new(x, 1u16))).unwrap();
+            }
+            gw_done_tx.send(()).unwrap();
+        });
+
+        //TUI: reads Position, writes Map. Lists Position first.
+        thread::spawn(move || {
+            for _ in 0..ITERATIONS {
+                let guard = tui_ecs_ap.req_access_set(
+                    AccessSet::new()
+                        .read::<Position>()
+                        .write_resource::<Map>(),
+                );
+
+                let positions = guard.read_storage::<Position>();
+                let mut map = guard.write_resource::<Map>();
+                map.blocked[11] = positions.get(entity).is_some(); //The entity's tile, (1, 1).
+            }
+            done_tx.send(()).unwrap();
+        });
+
+        for _ in 0..2 {
+            done_rx
+                .recv_timeout(Duration::from_secs(10))
+                .expect("GameWorld and TUI threads deadlocked on req_access_set()");
+        }
+    }
+
+    #[test]
+    fn test_writer_not_starved() {
+        const READERS: usize = 8;
+        const WRITES: u16 = 200;
+
+        let (ecs_ap, entity) = test_ecs_ap();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        //TUI: several readers, always overlapping, so that without writer
+        //preference the reader count would never drop to zero.
+        let mut reader_threads = Vec::new();
+        for _ in 0..READERS {
+            let tui_ecs_ap = ecs_ap.clone();
+            let stop = stop.clone();
+            reader_threads.push(thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let guard = tui_ecs_ap.req_access_set(AccessSet::new().read::<Position>());
+                    let positions = guard.read_storage::<Position>();
+                    assert!(positions.get(entity).is_some());
+                    thread::sleep(Duration::from_millis(1));
                 }
-            },
+            }));
+        }
 
-            (w, r) => {
-                panic!("This Condvar configuration should not be possible: ({}, {})", w, r)
-            },
+        //GameWorld: a single writer, which must get every one of its writes in.
+        let gw_ecs_ap = ecs_ap.clone();
+        let (done_tx, done_rx) = mpsc::channel();
+        thread::spawn(move || {
+            for i in 0..WRITES {
+                gw_ecs_ap
+                    .insert_component(Position(Coords::new(i, i)), entity)
+                    .unwrap();
+            }
+            done_tx.send(()).unwrap();
+        });
+
+        let result = done_rx.recv_timeout(Duration::from_secs(10));
+        stop.store(true, Ordering::Relaxed);
+        for reader in reader_threads {
+            reader.join().unwrap();
+        }
+
+        result.expect("GameWorld writer was starved by TUI readers");
+    }
+
+    #[test]
+    fn test_access_key_by_type() {
+        assert_eq!(AccessKey::of_component::<Position>(), AccessKey::of_component::<Position>());
+        assert_ne!(AccessKey::of_component::<Position>(), AccessKey::of_component::<Player>());
+        assert_ne!(AccessKey::of_component::<Position>(), AccessKey::of_resource::<Position>());
+        assert!(AccessKey::of_resource::<Map>().name().ends_with("Map"));
+    }
+
+    #[test]
+    fn test_read_guard_holds_lock_until_dropped() {
+        let (ecs_ap, entity) = test_ecs_ap();
+
+        let positions = ecs_ap.read::<Position>();
+
+        let gw_ecs_ap = ecs_ap.clone();
+        let (done_tx, done_rx) = mpsc::channel();
+        thread::spawn(move || {
+            gw_ecs_ap
+                .insert_component(Position(Coords::new(3u16, 3u16)), entity)
+                .unwrap();
+            done_tx.send(()).unwrap();
+        });
+
+        //The writer must not get in while the ReadGuard is alive...
+        assert!(done_rx.recv_timeout(Duration::from_millis(100)).is_err());
+        assert_eq!(positions.get(entity), Some(&Position(Coords::new(1u16, 1u16))));
+
+        //...and must get in as soon as it is dropped.
+        drop(positions);
+        done_rx
+            .recv_timeout(Duration::from_secs(10))
+            .expect("Dropping the ReadGuard did not release its lock");
+
+        let map = ecs_ap.write_resource::<Map>();
+        assert!(!map.blocked[11]);
+    }
+
+    //GameWorld-like System: writes Position, reads Map.
+    struct WanderSystem;
+
+    impl<'a> System<'a> for WanderSystem {
+        type SystemData = (ReadExpect<'a, Map>, WriteStorage<'a, Position>);
+
+        fn run(&mut self, (map, mut positions): Self::SystemData) {
+            for position in (&mut positions).join() {
+                position.0.x = (position.0.x + 1) % map.size;
+            }
+        }
+    }
+
+    //TUI-like System: reads Position, writes Map.
+    struct MarkDirtySystem;
+
+    impl<'a> System<'a> for MarkDirtySystem {
+        type SystemData = (ReadStorage<'a, Position>, WriteExpect<'a, Map>);
+
+        fn run(&mut self, (positions, mut map): Self::SystemData) {
+            map.blocked[11] = (&positions).join().count() > 0;
         }
+    }
+
+    #[test]
+    fn test_run_system_across_threads() {
+        const ITERATIONS: usize = 2_000;
+
+        let (ecs_ap, entity) = test_ecs_ap();
+        let gw_ecs_ap = ecs_ap.clone();
+        let tui_ecs_ap = ecs_ap.clone();
+        let (done_tx, done_rx) = mpsc::channel();
+        let gw_done_tx = done_tx.clone();
+
+        //specs panics on any conflicting borrow, so getting through
+        //this proves the right locks were taken for each System.
+        thread::spawn(move || {
+            let mut wander = WanderSystem;
+            for _ in 0..ITERATIONS {
+                gw_ecs_ap.run_system(&mut wander);
+            }
+            gw_done_tx.send(()).unwrap();
+        });
+
+        thread::spawn(move || {
+            let mut mark_dirty = MarkDirtySystem;
+            for _ in 0..ITERATIONS {
+                tui_ecs_ap.run_system(&mut mark_dirty);
+            }
+            done_tx.send(()).unwrap();
+        });
+
+        for _ in 0..2 {
+            done_rx
+                .recv_timeout(Duration::from_secs(10))
+                .expect("GameWorld and TUI threads deadlocked on run_system()");
+        }
+
+        let expected_x = ((1 + ITERATIONS) % 10) as u16;
+        assert_eq!(ecs_ap.read::<Position>().get(entity), Some(&Position(Coords::new(expected_x, 1u16))));
+        assert!(ecs_ap.read_resource::<Map>().blocked[11]);
+    }
+
+    #[test]
+    fn test_run_system_keys_get_names() {
+        let (ecs_ap, _) = test_ecs_ap();
+        let names = |ecs_ap: &ECSAccessPoint| -> Vec<&'static str> {
+            ecs_ap.access_stats().iter().map(|(key, _)| key.name()).collect()
+        };
+
+        //SystemData only gives ResourceIds, so the keys start out unnamed...
+        ecs_ap.run_system(&mut MarkDirtySystem);
+        assert!(names(&ecs_ap).iter().all(|name| *name == "(unnamed)"));
+
+        //...until the same things are reached by type.
+        drop(ecs_ap.read::<Position>());
+        drop(ecs_ap.read_resource::<Map>());
+        ecs_ap.run_system(&mut MarkDirtySystem);
+        let names = names(&ecs_ap);
+        assert!(names.iter().any(|name| name.ends_with("Position")));
+        assert!(names.iter().any(|name| name.ends_with("Map")));
+    }
+
+    #[test]
+    fn test_try_and_timeout_access() {
+        let (ecs_ap, _) = test_ecs_ap();
+
+        let positions = ecs_ap.read::<Position>();
+        assert!(ecs_ap.try_read::<Position>().is_ok());
+        assert!(matches!(ecs_ap.try_write::<Position>(), Err(Gremlin::AccessTimeout(_))));
+
+        let started = Instant::now();
+        match ecs_ap.write_timeout::<Position>(Duration::from_millis(50)) {
+            Err(Gremlin::AccessTimeout(key)) => assert_eq!(key, AccessKey::of_component::<Position>()),
+            _ => panic!("write_timeout() should have timed out"),
+        }
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        //A writer that gave up must not keep new readers out.
+        assert!(ecs_ap.try_read::<Position>().is_ok());
+
+        drop(positions);
+        let positions = ecs_ap.write::<Position>();
+        let timed_out = ecs_ap.read_timeout::<Position>(Duration::from_millis(10));
+        assert!(matches!(timed_out, Err(Gremlin::AccessTimeout(_))));
+        assert!(ecs_ap.read_resource_timeout::<Map>(Duration::from_millis(10)).is_ok());
+
+        drop(positions);
+        assert!(ecs_ap.try_write::<Position>().is_ok());
+    }
+
+    #[test]
+    fn test_failed_access_set_releases_held_keys() {
+        let (ecs_ap, _) = test_ecs_ap();
+
+        let positions = ecs_ap.write::<Position>();
+
+        let set = AccessSet::new().write_resource::<Map>().read::<Position>();
+        assert!(matches!(ecs_ap.try_req_access_set(set), Err(Gremlin::AccessTimeout(_))));
+        assert!(ecs_ap.try_write_resource::<Map>().is_ok());
+
+        drop(positions);
+        let set = AccessSet::new().write_resource::<Map>().read::<Position>();
+        assert!(ecs_ap.req_access_set_timeout(set, Duration::from_millis(50)).is_ok());
+    }
+
+    #[test]
+    fn test_poisoned_access() {
+        let (ecs_ap, _) = test_ecs_ap();
+
+        let gw_ecs_ap = ecs_ap.clone();
+        let panicked = thread::spawn(move || {
+            let _positions = gw_ecs_ap.write::<Position>();
+            panic!("GameWorld panicked mid-write");
+        })
+        .join();
+        assert!(panicked.is_err());
+
+        let key = match ecs_ap.try_read::<Position>() {
+            Err(Gremlin::AccessPoisoned(key)) => key,
+            _ => panic!("try_read() should have reported the poisoned lock"),
+        };
+        assert!(matches!(ecs_ap.write_timeout::<Position>(Duration::from_millis(10)), Err(Gremlin::AccessPoisoned(_))));
+
+        //Other locks are unaffected.
+        assert!(ecs_ap.try_read_resource::<Map>().is_ok());
+
+        ecs_ap.clear_poison(&key);
+        assert!(ecs_ap.try_write::<Position>().is_ok());
+    }
+
+    fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+        match payload.downcast::<String>() {
+            Ok(msg) => *msg,
+            Err(payload) => payload.downcast_ref::<&str>().unwrap_or(&"").to_string(),
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_deadlock_detected_across_threads() {
+        let (ecs_ap, _) = test_ecs_ap();
+        let barrier = Arc::new(Barrier::new(2));
+
+        let gw_ecs_ap = ecs_ap.clone();
+        let gw_barrier = barrier.clone();
+        let gw_thread = thread::Builder::new()
+            .name("gameworld".to_string())
+            .spawn(move || {
+                let _positions = gw_ecs_ap.write::<Position>();
+                gw_barrier.wait();
+                let _map = gw_ecs_ap.write_resource::<Map>();
+            })
+            .unwrap();
+
+        let tui_ecs_ap = ecs_ap.clone();
+        let tui_thread = thread::Builder::new()
+            .name("tui".to_string())
+            .spawn(move || {
+                let _map = tui_ecs_ap.write_resource::<Map>();
+                barrier.wait();
+                let _positions = tui_ecs_ap.write::<Position>();
+            })
+            .unwrap();
+
+        //Whichever thread closes the cycle panics. The other one then gets in,
+        //but finds the lock poisoned by that panic.
+        let reports: Vec<String> = vec![gw_thread.join(), tui_thread.join()]
+            .into_iter()
+            .filter_map(|result| result.err())
+            .map(panic_message)
+            .filter(|msg| msg.starts_with("Deadlock detected"))
+            .collect();
+
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+        assert!(report.contains("thread 'gameworld'"));
+        assert!(report.contains("thread 'tui'"));
+        assert!(report.contains("Position"));
+        assert!(report.contains("Map"));
+        assert!(report.contains(file!()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_deadlock_detected_on_self() {
+        let (ecs_ap, _) = test_ecs_ap();
+
+        let result = thread::spawn(move || {
+            let _positions = ecs_ap.read::<Position>();
+            let _positions_again = ecs_ap.write::<Position>();
+        })
+        .join();
+
+        let report = panic_message(result.unwrap_err());
+        assert!(report.starts_with("Deadlock detected"));
+        assert!(report.contains("waits for Write access to goblin_rl::gameworld::components::Position"));
+        assert!(report.contains("holds Read access to goblin_rl::gameworld::components::Position"));
+    }
+
+    #[test]
+    fn test_access_stats() {
+        let (ecs_ap, _) = test_ecs_ap();
+
+        drop(ecs_ap.read::<Position>());
+        drop(ecs_ap.read::<Position>());
+
+        //A write which has to wait ~20ms for a read held ~20ms.
+        let positions = ecs_ap.read::<Position>();
+        let gw_ecs_ap = ecs_ap.clone();
+        let writer = thread::spawn(move || drop(gw_ecs_ap.write::<Position>()));
+        thread::sleep(Duration::from_millis(20));
+        drop(positions);
+        writer.join().unwrap();
+
+        let stats = ecs_ap.access_stats();
+        let (key, position_stats) = &stats[0];
+        assert_eq!(stats.len(), 1);
+        assert_eq!(*key, AccessKey::of_component::<Position>());
+        assert_eq!(position_stats.read.acquisitions, 3);
+        assert_eq!(position_stats.write.acquisitions, 1);
+        assert!(position_stats.read.total_hold >= Duration::from_millis(20));
+        assert!(position_stats.write.max_wait > Duration::ZERO);
+        assert_eq!(position_stats.write.max_wait, position_stats.write.total_wait);
+
+        let mut report: Vec<u8> = Vec::new();
+        access_stats::write_report(&mut report, &stats).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert_eq!(report.lines().count(), 4);
+        assert!(report.lines().nth(3).unwrap().contains("Position"));
+    }
+
+    #[test]
+    fn test_entity_lifecycle() {
+        let (ecs_ap, _) = test_ecs_ap();
+
+        let goblin = ecs_ap
+            .create_entity()
+            .with(Position(Coords::new(3u16, 4u16)))
+            .build();
+        assert!(ecs_ap.is_alive(goblin));
+        assert_eq!(ecs_ap.read::<Position>().get(goblin).unwrap().0, Coords::new(3u16, 4u16));
+
+        //Deletion only takes effect once maintain() has run.
+        ecs_ap.delete_entity(goblin).unwrap();
+        assert!(ecs_ap.read::<Position>().get(goblin).is_some());
+        ecs_ap.maintain();
+        assert!(!ecs_ap.is_alive(goblin));
+        assert!(ecs_ap.read::<Position>().get(goblin).is_none());
+        assert!(ecs_ap.delete_entity(goblin).is_err());
+
+        //A builder dropped without build() takes its entity with it.
+        let players_before = (&ecs_ap.read::<Player>()).join().count();
+        drop(ecs_ap.create_entity().with(Player {}));
+        ecs_ap.maintain();
+        assert_eq!((&ecs_ap.read::<Player>()).join().count(), players_before);
+        assert_eq!((&*ecs_ap.read_resource::<EntitiesRes>()).join().count(), 1);
+    }
+
+    #[test]
+    fn test_maintain_waits_for_guards() {
+        let (ecs_ap, entity) = test_ecs_ap();
+        let maintained = Arc::new(AtomicBool::new(false));
+
+        let positions = ecs_ap.read::<Position>();
+        ecs_ap.delete_entity(entity).unwrap();
+
+        let gw_ecs_ap = ecs_ap.clone();
+        let gw_maintained = maintained.clone();
+        let maintainer = thread::spawn(move || {
+            gw_ecs_ap.maintain();
+            gw_maintained.store(true, Ordering::SeqCst);
+        });
+
+        thread::sleep(Duration::from_millis(20));
+        assert!(!maintained.load(Ordering::SeqCst));
+        assert!(positions.get(entity).is_some());
+
+        //Another guard can still be taken while maintain() is only waiting.
+        drop(ecs_ap.read::<Player>());
+
+        drop(positions);
+        maintainer.join().unwrap();
+        assert!(maintained.load(Ordering::SeqCst));
+        assert!(ecs_ap.read::<Position>().get(entity).is_none());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "waits for every guard to be dropped")]
+    fn test_maintain_while_holding_guard() {
+        let (ecs_ap, _) = test_ecs_ap();
+
+        let _positions = ecs_ap.read::<Position>();
+        ecs_ap.maintain();
+    }
+
+    #[test]
+    fn test_collect_deltas() {
+        let (ecs_ap, existing) = test_ecs_ap();
+        ecs_ap.track::<Position>();
+
+        //Mutably accessed, but unchanged, so not reported.
+        ecs_ap.write::<Position>().get_mut(existing).unwrap();
+        assert!(ecs_ap.collect_deltas().is_empty());
+
+        //Added and moved since the last collection, so only reported as added.
+        let goblin = ecs_ap.create_entity().with(Position(Coords::new(5u16, 5u16))).build();
+        ecs_ap.write::<Position>().get_mut(goblin).unwrap().0 = Coords::new(5u16, 6u16);
+        ecs_ap.insert_component(Position(Coords::new(2u16, 2u16)), existing).unwrap();
+        assert_eq!(
+            ecs_ap.collect_deltas(),
+            vec![
+                DeltaNotification::ComponentAdded { entity: goblin, component: "Position" },
+                DeltaNotification::EntityMoved {
+                    entity: existing,
+                    from: Coords::new(1u16, 1u16),
+                    to: Coords::new(2u16, 2u16),
+                },
+            ]
+        );
 
+        ecs_ap.write::<Position>().get_mut(goblin).unwrap().0 = Coords::new(5u16, 7u16);
+        assert_eq!(
+            ecs_ap.collect_deltas(),
+            vec![DeltaNotification::EntityMoved {
+                entity: goblin,
+                from: Coords::new(5u16, 6u16),
+                to: Coords::new(5u16, 7u16),
+            }]
+        );
 
-        self.cvar.notify_all();
+        ecs_ap.delete_entity(goblin).unwrap();
+        ecs_ap.maintain();
+        assert_eq!(
+            ecs_ap.collect_deltas(),
+            vec![DeltaNotification::ComponentRemoved { entity: goblin, component: "Position" }]
+        );
+        assert!(ecs_ap.collect_deltas().is_empty());
     }
 }
@@ -0,0 +1,122 @@
+//Jerome M. St.Martin
+//June 17, 2022
+
+//-----------------------------------------------------------------------------
+//------------- Controls Atomic Access to Several ECS Storages ----------------
+//----------------------------- and/or Resources ------------------------------
+//-----------------------------------------------------------------------------
+
+#[cfg(test)]
+use specs::prelude::WriteStorage;
+use specs::{
+    prelude::{ReadStorage, Resource},
+    shred::{Fetch, FetchMut, ResourceId},
+    Component, WorldExt,
+};
+
+use super::{AccessGuard, AccessKey, GatePass};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AccessMode {
+    Read,
+    Write,
+}
+
+///The set of keys to be acquired all at once by ECSAccessPoint::req_access_set().
+///The order in which keys are added does not matter.
+#[derive(Default, Debug)]
+pub struct AccessSet {
+    pub(super) reads: Vec<AccessKey>,
+    pub(super) writes: Vec<AccessKey>,
+}
+
+impl AccessSet {
+    pub fn new() -> Self {
+        AccessSet::default()
+    }
+
+    pub(super) fn from_resource_ids(reads: Vec<ResourceId>, writes: Vec<ResourceId>) -> Self {
+        AccessSet {
+            reads: reads.into_iter().map(AccessKey::of_resource_id).collect(),
+            writes: writes.into_iter().map(AccessKey::of_resource_id).collect(),
+        }
+    }
+
+    pub fn read<T: Component>(mut self) -> Self {
+        self.reads.push(AccessKey::of_component::<T>());
+        self
+    }
+
+    //Components are only written by Systems, through run_system(), outside of the tests.
+    #[cfg(test)]
+    pub fn write<T: Component>(mut self) -> Self {
+        self.writes.push(AccessKey::of_component::<T>());
+        self
+    }
+
+    pub fn read_resource<T: Resource>(mut self) -> Self {
+        self.reads.push(AccessKey::of_resource::<T>());
+        self
+    }
+
+    pub fn write_resource<T: Resource>(mut self) -> Self {
+        self.writes.push(AccessKey::of_resource::<T>());
+        self
+    }
+}
+
+///Holds every AccessGuard of an AccessSet; all of them are released when this is dropped.
+///Handed-out storages and resources borrow from this guard, so they cannot outlive it.
+pub struct MultiAccessGuard<'a> {
+    ecs: &'a specs::World,
+    held: Vec<(AccessKey, AccessMode, AccessGuard)>,
+    _pass: GatePass,
+}
+
+impl<'a> MultiAccessGuard<'a> {
+    pub(super) fn new(
+        ecs: &'a specs::World,
+        held: Vec<(AccessKey, AccessMode, AccessGuard)>,
+        pass: GatePass,
+    ) -> Self {
+        MultiAccessGuard { ecs, held, _pass: pass }
+    }
+
+    pub fn read_storage<T: Component>(&self) -> ReadStorage<'_, T> {
+        self.assert_held(AccessKey::of_component::<T>(), AccessMode::Read);
+        self.ecs.read_component()
+    }
+
+    #[cfg(test)]
+    pub fn write_storage<T: Component>(&self) -> WriteStorage<'_, T> {
+        self.assert_held(AccessKey::of_component::<T>(), AccessMode::Write);
+        self.ecs.write_component()
+    }
+
+    pub fn read_resource<T: Resource>(&self) -> Fetch<'_, T> {
+        self.assert_held(AccessKey::of_resource::<T>(), AccessMode::Read);
+        self.ecs.fetch()
+    }
+
+    pub fn write_resource<T: Resource>(&self) -> FetchMut<'_, T> {
+        self.assert_held(AccessKey::of_resource::<T>(), AccessMode::Write);
+        self.ecs.fetch_mut()
+    }
+
+    //A key held for writing may also be read from, but not the other way around.
+    fn assert_held(&self, key: AccessKey, wanted: AccessMode) {
+        let held_mode = self
+            .held
+            .iter()
+            .find(|(held_key, _, _)| *held_key == key)
+            .map(|(_, mode, _)| *mode);
+
+        match (held_mode, wanted) {
+            (Some(AccessMode::Write), _) => {}
+            (Some(AccessMode::Read), AccessMode::Read) => {}
+            (held, wanted) => {
+                panic!("MultiAccessGuard does not hold {:?} for {:?} (held as {:?})", key, wanted, held)
+            }
+        }
+    }
+}
@@ -5,49 +5,76 @@
 //---------------- Controls Access to Individual ECS Resources ----------------
 //-----------------------------------------------------------------------------
 
+use std::ops::{Deref, DerefMut};
+use std::time::Instant;
+
 use specs::{
     prelude::Resource,
     shred::{Fetch, FetchMut},
 }; 
 
-use super::{AccessorState, AccessGuard};
+use super::AccessGuard;
+use crate::error::Gremlin;
 
-pub trait ResourceAccessGuard<'a> {
-    fn read_resource<T: Resource>(&self, ecs: &'a specs::World) -> Fetch<'a, T>;
-    fn write_resource<T: Resource>(&self, ecs: &'a specs::World) -> FetchMut<'a, T>;
+///Consumes the AccessGuard, so that the lock lives exactly as long as the resource borrow.
+pub(super) trait ResourceAccessGuard<'a> {
+    fn read_resource<T: Resource>(self, ecs: &'a specs::World, deadline: Option<Instant>) -> Result<FetchGuard<'a, T>, Gremlin>;
+    fn write_resource<T: Resource>(self, ecs: &'a specs::World, deadline: Option<Instant>) -> Result<FetchMutGuard<'a, T>, Gremlin>;
 }
 
 impl<'a> ResourceAccessGuard<'a> for AccessGuard {
-    fn read_resource<T: Resource>(&self, ecs: &'a specs::World) -> Fetch<'a, T> {
-        const READ_ERR_MSG: &str = "ResourceAccessGuard mutex poisoned before read.";
-        
-        let mut accessor_state: std::sync::MutexGuard<'_, AccessorState> = self
-            .cvar
-            .wait_while(self.mtx.lock().expect(READ_ERR_MSG), |acc_state: &mut AccessorState| {
-                !acc_state.read_allowed
-            })
-        .expect(READ_ERR_MSG);
-
-        accessor_state.write_allowed = false;
-        accessor_state.read_allowed = true;
-        accessor_state.readers += 1;
-
-        ecs.fetch()
+    #[track_caller]
+    fn read_resource<T: Resource>(mut self, ecs: &'a specs::World, deadline: Option<Instant>) -> Result<FetchGuard<'a, T>, Gremlin> {
+        self.lock_read(deadline)?;
+        Ok(FetchGuard {
+            fetch: ecs.fetch(),
+            _lock: self,
+        })
+    }
+
+    #[track_caller]
+    fn write_resource<T: Resource>(mut self, ecs: &'a specs::World, deadline: Option<Instant>) -> Result<FetchMutGuard<'a, T>, Gremlin> {
+        self.lock_write(deadline)?;
+        Ok(FetchMutGuard {
+            fetch: ecs.fetch_mut(),
+            _lock: self,
+        })
+    }
+}
+
+//Fields are dropped in declaration order, so the resource borrow
+//is always given back before the lock is released.
+
+///Owns both Read access and the Fetch; derefs to the resource.
+pub struct FetchGuard<'a, T: Resource> {
+    fetch: Fetch<'a, T>,
+    _lock: AccessGuard,
+}
+
+impl<'a, T: Resource> Deref for FetchGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.fetch
     }
+}
 
-    fn write_resource<T: Resource>(&self, ecs: &'a specs::World) -> FetchMut<'a, T> {
-        const WRITE_ERR_MSG: &str = "ResourceAccessGuard mutex poisoned before write.";
+///Owns both exclusive Write access and the FetchMut; derefs to the resource.
+pub struct FetchMutGuard<'a, T: Resource> {
+    fetch: FetchMut<'a, T>,
+    _lock: AccessGuard,
+}
 
-        let mut accessor_state: std::sync::MutexGuard<'_, AccessorState> = self
-            .cvar
-            .wait_while(self.mtx.lock().expect(WRITE_ERR_MSG), |acc_state: &mut AccessorState| {
-                !acc_state.read_allowed
-            })
-        .expect(WRITE_ERR_MSG);
+impl<'a, T: Resource> Deref for FetchMutGuard<'a, T> {
+    type Target = T;
 
-        accessor_state.read_allowed = false;
-        accessor_state.write_allowed = false;
+    fn deref(&self) -> &Self::Target {
+        &self.fetch
+    }
+}
 
-        ecs.fetch_mut()
+impl<'a, T: Resource> DerefMut for FetchMutGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.fetch
     }
 }
@@ -5,55 +5,76 @@
 //---------------- Controls Access to Individual ECS Storages -----------------
 //-----------------------------------------------------------------------------
 
+use std::ops::{Deref, DerefMut};
+use std::time::Instant;
+
 use specs::{
     prelude::{ReadStorage, WriteStorage},
     Component, WorldExt,
 };
 
-use super::{AccessorState, AccessGuard};
+use super::AccessGuard;
+use crate::error::Gremlin;
 
-pub trait StorageAccessGuard<'a> {
-    fn read_storage<T: Component>(&self, ecs: &'a specs::World) -> ReadStorage<'a, T>;
-    fn write_storage<T: Component>(&self, ecs: &'a specs::World) -> WriteStorage<'a, T>;
+///Consumes the AccessGuard, so that the lock lives exactly as long as the storage borrow.
+pub(super) trait StorageAccessGuard<'a> {
+    fn read_storage<T: Component>(self, ecs: &'a specs::World, deadline: Option<Instant>) -> Result<ReadGuard<'a, T>, Gremlin>;
+    fn write_storage<T: Component>(self, ecs: &'a specs::World, deadline: Option<Instant>) -> Result<WriteGuard<'a, T>, Gremlin>;
 }
 
 impl<'a> StorageAccessGuard<'a> for AccessGuard {
-    fn read_storage<T: Component>(&self, ecs: &'a specs::World) -> ReadStorage<'a, T> {
-        const READ_ERR_MSG: &str = "StorageAccessGuard mutex poisoned before read.";
-
-        //While read access is NOT allowed, wait until the calling thread is notified on the
-        //condvar. Once the condvar (cvar) is notified, the calling thread is awoken,
-        //the lock for the mutex (mtx) is acquired, and execution of this function continues.
-        let mut accessor_state: std::sync::MutexGuard<'_, AccessorState> = self
-            .cvar
-            .wait_while(self.mtx.lock().expect(READ_ERR_MSG), |acc_state: &mut AccessorState| {
-                !acc_state.read_allowed
-            })
-            .expect(READ_ERR_MSG);
-
-        accessor_state.read_allowed = true;
-        accessor_state.write_allowed = false;
-        accessor_state.readers += 1;
-
-        ecs.read_component()
+    #[track_caller]
+    fn read_storage<T: Component>(mut self, ecs: &'a specs::World, deadline: Option<Instant>) -> Result<ReadGuard<'a, T>, Gremlin> {
+        self.lock_read(deadline)?;
+        Ok(ReadGuard {
+            storage: ecs.read_component(),
+            _lock: self,
+        })
+    }
+
+    #[track_caller]
+    fn write_storage<T: Component>(mut self, ecs: &'a specs::World, deadline: Option<Instant>) -> Result<WriteGuard<'a, T>, Gremlin> {
+        self.lock_write(deadline)?;
+        Ok(WriteGuard {
+            storage: ecs.write_component(),
+            _lock: self,
+        })
+    }
+}
+
+//Fields are dropped in declaration order, so the storage borrow
+//is always given back before the lock is released.
+
+///Owns both Read access and the ReadStorage; derefs to the ReadStorage.
+pub struct ReadGuard<'a, T: Component> {
+    storage: ReadStorage<'a, T>,
+    _lock: AccessGuard,
+}
+
+impl<'a, T: Component> Deref for ReadGuard<'a, T> {
+    type Target = ReadStorage<'a, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.storage
     }
+}
 
-    fn write_storage<T: Component>(&self, ecs: &'a specs::World) -> WriteStorage<'a, T> {
-        const WRITE_ERR_MSG: &str = "StorageAccessGuard mutex poisoned before write.";
+///Owns both exclusive Write access and the WriteStorage; derefs to the WriteStorage.
+pub struct WriteGuard<'a, T: Component> {
+    storage: WriteStorage<'a, T>,
+    _lock: AccessGuard,
+}
 
-        /*While write access is NOT allowed, wait until the calling thread is notified on the
-         * condvar. Once the condvar is notified, the calling thread is awoken,
-         * the lock for the mutex is acquired, and the execution of this function continues.*/
-        let mut accessor_state: std::sync::MutexGuard<'_, AccessorState> = self
-            .cvar
-            .wait_while(self.mtx.lock().expect(WRITE_ERR_MSG), |acc_state: &mut AccessorState| {
-                !acc_state.write_allowed
-            })
-            .expect(WRITE_ERR_MSG);
+impl<'a, T: Component> Deref for WriteGuard<'a, T> {
+    type Target = WriteStorage<'a, T>;
 
-        accessor_state.read_allowed = false;
-        accessor_state.write_allowed = false;
+    fn deref(&self) -> &Self::Target {
+        &self.storage
+    }
+}
 
-        ecs.write_component()
+impl<'a, T: Component> DerefMut for WriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.storage
     }
 }
@@ -0,0 +1,101 @@
+//Jerome M. St.Martin
+//June 22, 2022
+
+//-----------------------------------------------------------------------------
+//------------- Exclusive Access to the Whole World, for maintain() -----------
+//-----------------------------------------------------------------------------
+
+/* specs::World::maintain() needs &mut World, which nobody can have while any
+ * AccessGuard is alive. Every AccessGuard passes through this gate for as long
+ * as it exists, by holding a GatePass; maintain() waits until the gate is empty,
+ * then closes it.
+ *
+ * Unlike the Accessors, this is reader-preferring: a pending maintain() does
+ * not keep new guards out, only a running one does. That way a thread which
+ * already holds a guard can always take another one, and maintain() only has
+ * to wait for a moment where nobody is using the ECS, e.g. between ticks.
+ */
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+#[derive(Default, Debug)]
+struct GateState {
+    guards: usize,
+    maintaining: bool,
+}
+
+#[derive(Default)]
+pub(super) struct WorldGate {
+    mtx: Mutex<GateState>,
+    cvar: Condvar,
+}
+
+impl WorldGate {
+    pub(super) fn new() -> Self {
+        WorldGate::default()
+    }
+
+    ///Blocks only while a maintain() is actually running.
+    fn enter(&self) {
+        let mut state = self
+            .cvar
+            .wait_while(self.state(), |state: &mut GateState| state.maintaining)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        state.guards += 1;
+    }
+
+    fn exit(&self) {
+        let mut state = self.state();
+        state.guards -= 1;
+
+        if state.guards == 0 {
+            self.cvar.notify_all();
+        }
+    }
+
+    ///Blocks until no GatePass is alive, then keeps new ones out until the
+    ///returned Maintenance is dropped, even if maintain() panics.
+    pub(super) fn open_maintenance(&self) -> Maintenance<'_> {
+        let mut state = self
+            .cvar
+            .wait_while(self.state(), |state: &mut GateState| {
+                state.guards > 0 || state.maintaining
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+
+        state.maintaining = true;
+        Maintenance(self)
+    }
+
+    //GateState is only ever left consistent, so poisoning is ignored.
+    fn state(&self) -> MutexGuard<'_, GateState> {
+        self.mtx.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+///Held by every AccessGuard and MultiAccessGuard for as long as it exists.
+///Nothing may touch the World without one.
+pub(super) struct GatePass(Arc<WorldGate>);
+
+impl GatePass {
+    pub(super) fn new(gate: Arc<WorldGate>) -> Self {
+        gate.enter();
+        GatePass(gate)
+    }
+}
+
+impl Drop for GatePass {
+    fn drop(&mut self) {
+        self.0.exit();
+    }
+}
+
+pub(super) struct Maintenance<'a>(&'a WorldGate);
+
+impl<'a> Drop for Maintenance<'a> {
+    fn drop(&mut self) {
+        self.0.state().maintaining = false;
+        self.0.cvar.notify_all();
+    }
+}
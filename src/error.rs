@@ -2,8 +2,14 @@
 //May, 2022
 
 use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use super::common::{DeltaNotification, InputEvent, MutateCommand};
+use super::common::{ControlRequest, DeltaNotification, InputEvent, ModelEvent, RunState};
+use super::ecs_access_point::AccessKey;
 
 //-------------------------------------------
 //------------ Custom Err Type ------------
@@ -16,29 +22,109 @@ pub enum Gremlin {
     //Internal Errs
     InvalidInput,
     OutOfMapBounds,
+    AccessTimeout(AccessKey),  //ECSAccessPoint could not grant access in time.
+    AccessPoisoned(AccessKey), //A thread panicked while holding Write access.
+    InvalidArgs(String),       //Bad command-line options; says what was wrong.
+    InvalidKeybindings(String), //Bad keybindings file; says what was wrong.
+    IllegalTransition(RunState, RunState), //From, to; not an edge of the RunState graph.
+    ThreadStopped(&'static str), //The named thread ended without being told to.
 
     //Outside Errs w/ Source Fields
     IOErr(std::io::Error),
     IESendErr(std::sync::mpsc::SendError<InputEvent>),
-    MCSendErr(std::sync::mpsc::SendError<MutateCommand>),
+    MCSendErr(std::sync::mpsc::SendError<ModelEvent>),
     DNSendErr(std::sync::mpsc::SendError<DeltaNotification>),
+    RSSendErr(std::sync::mpsc::SendError<RunState>),
+    CRSendErr(std::sync::mpsc::SendError<ControlRequest>),
     RecvErr(std::sync::mpsc::RecvError),
     SpecsErr(specs::error::Error),
 }
 
+//Appended to, next to wherever the game was launched from, once anything goes wrong.
+const ERROR_LOG_PATH: &str = "error_log.txt";
+
+impl Gremlin {
+    ///Whether the thread that got this cannot go on. Every mpsc error means the
+    ///thread on the other end is gone, and an IOErr means the terminal is.
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            Gremlin::InvalidInput
+            | Gremlin::OutOfMapBounds
+            | Gremlin::AccessTimeout(_)
+            | Gremlin::IllegalTransition(_, _)
+            | Gremlin::SpecsErr(_) => false,
+
+            Gremlin::AccessPoisoned(_)
+            | Gremlin::InvalidArgs(_)
+            | Gremlin::InvalidKeybindings(_)
+            | Gremlin::ThreadStopped(_)
+            | Gremlin::IOErr(_)
+            | Gremlin::IESendErr(_)
+            | Gremlin::MCSendErr(_)
+            | Gremlin::DNSendErr(_)
+            | Gremlin::RSSendErr(_)
+            | Gremlin::CRSendErr(_)
+            | Gremlin::RecvErr(_) => true,
+        }
+    }
+}
+
+///Fatal or not, every Gremlin a tick loop gets is written down here.
+pub fn log_error(e: &Gremlin) {
+    //Nowhere left to report a failure to report.
+    let _ = log_error_to(ERROR_LOG_PATH, e);
+}
+
+fn log_error_to<P: AsRef<Path>>(path: P, e: &Gremlin) -> Result<(), Gremlin> {
+    let unix_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let current = thread::current();
+    let severity = if e.is_fatal() { "fatal" } else { "recoverable" };
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(
+        file,
+        "{}\t{}\t{}\t{}",
+        unix_secs,
+        current.name().unwrap_or("(unnamed)"),
+        severity,
+        e
+    )?;
+    Ok(())
+}
+
 impl fmt::Display for Gremlin {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Gremlin::InvalidInput => write!(f, "invalid input"),
+            Gremlin::OutOfMapBounds => write!(f, "out of map bounds"),
+            Gremlin::AccessTimeout(key) => write!(f, "timed out waiting for {}", key.name()),
+            Gremlin::AccessPoisoned(key) => write!(f, "{} was poisoned by a panic mid-write", key.name()),
+            Gremlin::InvalidArgs(why) | Gremlin::InvalidKeybindings(why) => write!(f, "{}", why),
+            Gremlin::IllegalTransition(from, to) => write!(f, "no transition from {:?} to {:?}", from, to),
+            Gremlin::ThreadStopped(name) => write!(f, "the {} thread stopped", name),
+            Gremlin::IOErr(source) => write!(f, "I/O error: {}", source),
+            Gremlin::IESendErr(source) => write!(f, "InputEvent {}", source),
+            Gremlin::MCSendErr(source) => write!(f, "ModelEvent {}", source),
+            Gremlin::DNSendErr(source) => write!(f, "DeltaNotification {}", source),
+            Gremlin::RSSendErr(source) => write!(f, "RunState {}", source),
+            Gremlin::CRSendErr(source) => write!(f, "ControlRequest {}", source),
+            Gremlin::RecvErr(source) => write!(f, "{}", source),
+            Gremlin::SpecsErr(source) => write!(f, "specs: {}", source),
+        }
     }
 }
 
-impl<'a> std::error::Error for Gremlin {
+impl std::error::Error for Gremlin {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Gremlin::IOErr(source) => Some(source),
             Gremlin::IESendErr(source) => Some(source),
             Gremlin::MCSendErr(source) => Some(source),
             Gremlin::DNSendErr(source) => Some(source),
+            Gremlin::RSSendErr(source) => Some(source),
+            Gremlin::CRSendErr(source) => Some(source),
             Gremlin::RecvErr(source) => Some(source),
             Gremlin::SpecsErr(source) => Some(source),
             _ => None,
@@ -46,38 +132,98 @@ impl<'a> std::error::Error for Gremlin {
     }
 }
 
-impl<'a> From<std::io::Error> for Gremlin {
+impl From<std::io::Error> for Gremlin {
     fn from(item: std::io::Error) -> Self {
         Gremlin::IOErr(item)
     }
 }
 
-impl<'a> From<std::sync::mpsc::SendError<InputEvent>> for Gremlin {
+impl From<std::sync::mpsc::SendError<InputEvent>> for Gremlin {
     fn from(item: std::sync::mpsc::SendError<InputEvent>) -> Self {
         Gremlin::IESendErr(item)
     }
 }
 
-impl<'a> From<std::sync::mpsc::SendError<MutateCommand>> for Gremlin {
-    fn from(item: std::sync::mpsc::SendError<MutateCommand>) -> Self {
+impl From<std::sync::mpsc::SendError<ModelEvent>> for Gremlin {
+    fn from(item: std::sync::mpsc::SendError<ModelEvent>) -> Self {
         Gremlin::MCSendErr(item)
     }
 }
 
-impl<'a> From<std::sync::mpsc::SendError<DeltaNotification>> for Gremlin {
+impl From<std::sync::mpsc::SendError<DeltaNotification>> for Gremlin {
     fn from(item: std::sync::mpsc::SendError<DeltaNotification>) -> Self {
         Gremlin::DNSendErr(item)
     }
 }
 
-impl<'a> From<std::sync::mpsc::RecvError> for Gremlin {
+impl From<std::sync::mpsc::SendError<RunState>> for Gremlin {
+    fn from(item: std::sync::mpsc::SendError<RunState>) -> Self {
+        Gremlin::RSSendErr(item)
+    }
+}
+
+impl From<std::sync::mpsc::SendError<ControlRequest>> for Gremlin {
+    fn from(item: std::sync::mpsc::SendError<ControlRequest>) -> Self {
+        Gremlin::CRSendErr(item)
+    }
+}
+
+impl From<std::sync::mpsc::RecvError> for Gremlin {
     fn from(item: std::sync::mpsc::RecvError) -> Self {
         Gremlin::RecvErr(item)
     }
 }
 
-impl<'a> From<specs::error::Error> for Gremlin {
+impl From<specs::error::Error> for Gremlin {
     fn from(item: specs::error::Error) -> Self {
         Gremlin::SpecsErr(item)
     }
 }
+
+#[cfg(test)]
+mod test {
+
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn test_is_fatal() {
+        let (tx, rx) = mpsc::channel::<InputEvent>();
+        drop(rx);
+        let disconnected: Gremlin = tx.send(InputEvent::Null).unwrap_err().into();
+        assert!(disconnected.is_fatal());
+        assert!(Gremlin::ThreadStopped("tui").is_fatal());
+
+        assert!(!Gremlin::OutOfMapBounds.is_fatal());
+        assert!(!Gremlin::IllegalTransition(RunState::MainMenu, RunState::GameOver).is_fatal());
+    }
+
+    #[test]
+    fn test_display() {
+        use crate::gameworld::resources::map::Map;
+
+        let timeout = Gremlin::AccessTimeout(AccessKey::of_resource::<Map>());
+        assert!(timeout.to_string().starts_with("timed out waiting for "));
+        assert!(timeout.to_string().ends_with("Map"));
+        assert_eq!(
+            Gremlin::IllegalTransition(RunState::MainMenu, RunState::GameOver).to_string(),
+            "no transition from MainMenu to GameOver"
+        );
+        assert_eq!(Gremlin::InvalidArgs("unknown option --x".to_string()).to_string(), "unknown option --x");
+    }
+
+    #[test]
+    fn test_log_error() {
+        let path = std::env::temp_dir().join(format!("goblin_rl_errors_{}.txt", std::process::id()));
+        log_error_to(&path, &Gremlin::OutOfMapBounds).unwrap();
+        log_error_to(&path, &Gremlin::ThreadStopped("gameworld")).unwrap();
+
+        let logged = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = logged.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("\trecoverable\tout of map bounds"));
+        assert!(lines[1].ends_with("\tfatal\tthe gameworld thread stopped"));
+    }
+}
@@ -8,18 +8,91 @@
 use specs::prelude::*;
 use specs_derive::Component;
 
-use crate::common::Coords;
+use crate::common::{Coords, DeltaNotification};
+use crate::ecs_access_point::{ECSAccessPoint, TrackedComponent};
 
 pub(crate) fn register_all_components(w: &mut specs::World) {
     w.register::<Player>();
     w.register::<Position>();
+    w.register::<Renderable>();
+    w.register::<Name>();
+    w.register::<Item>();
+    w.register::<Carried>();
+    w.register::<Consumable>();
+    w.register::<Aimed>();
+    w.register::<Door>();
+    w.register::<Stairs>();
+}
+
+///Changes to these are sent to the TUI as DeltaNotifications at the end of each tick.
+pub(crate) fn track_all_components(ecs_ap: &ECSAccessPoint) {
+    ecs_ap.track::<Position>();
+    ecs_ap.track::<Renderable>();
 }
 
 // Marker/Stateless Components
 #[derive(Debug, PartialEq, Eq, Hash, Component)]
 pub struct Player {}
 
+#[derive(Debug, PartialEq, Eq, Hash, Component)]
+pub struct Item {}
+
+#[derive(Debug, PartialEq, Eq, Hash, Component)]
+pub struct Consumable {} //Used up, i.e. deleted, when used.
+
+#[derive(Debug, PartialEq, Eq, Hash, Component)]
+pub struct Aimed {} //Used on a Target, which must be given; nothing else can be aimed.
+
 // Stateful Components
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Position(pub Coords);
+
+impl Component for Position {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
+impl TrackedComponent for Position {
+    const NAME: &'static str = "Position";
+
+    fn changed(entity: Entity, old: &Self, new: &Self) -> Option<DeltaNotification> {
+        Some(DeltaNotification::EntityMoved {
+            entity,
+            from: old.0,
+            to: new.0,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Renderable {
+    pub glyph: char,
+}
+
+impl Component for Renderable {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
+impl TrackedComponent for Renderable {
+    const NAME: &'static str = "Renderable";
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Component)]
+pub struct Name(pub String);
+
+//An Item has either a Position or a Carried, never both.
 #[derive(Debug, PartialEq, Eq, Hash, Component)]
-pub struct Position(Coords);
+pub struct Carried {
+    pub by: Entity,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Component)]
+pub struct Door {
+    pub open: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Component)]
+pub enum Stairs {
+    Down,
+    Up,
+}
 
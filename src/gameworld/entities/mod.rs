@@ -6,13 +6,17 @@
 //------------------------------ for ECS Entities -----------------------------
 //-----------------------------------------------------------------------------
 
-use specs::{Component, Entity};
+use specs::Entity;
+
+use crate::common::Coords;
+use crate::ecs_access_point::ECSAccessPoint;
 use super::components::*;
 
-pub(crate) fn build_player_entity(ecs: &mut specs::World, spawn_at: Coords) -> Entity {
-    ecs
+pub(crate) fn build_player_entity(ecs_ap: &ECSAccessPoint, spawn_at: Coords) -> Entity {
+    ecs_ap
         .create_entity()
         .with(Player {})
         .with(Position(spawn_at))
+        .with(Renderable { glyph: '@' })
         .build()
 }
@@ -2,15 +2,35 @@
 //May, 2022
 
 use std::sync::{
-    mpsc::{Receiver, SyncSender},
+    mpsc::{Receiver, Sender},
     Arc,
 };
+use std::time::Duration;
 
 //specs lib docs say this should be imported over just World
 
-use crate::common::{DeltaNotification, MutateCommand, Ticker};
-use crate::ecs_access_point::ECSAccessPoint;
+use specs::{world::EntitiesRes, Entity, Join};
+
+use crate::channel_trace;
+use crate::common::{
+    CommandOutcome, CommandReply, CommandRequest, ControlRequest, Coords, DeltaNotification, Dir, ModelEvent,
+    MutateCommand, Rejection, RenderSnapshot, RunState, SnapshotSlot, TickLoop, Ticker, ViewSender,
+};
+use crate::ecs_access_point::{AccessSet, ECSAccessPoint};
 use crate::error::Gremlin;
+use components::{Player, Position};
+use resources::{game_log::GameLog, map::Map, Depth, GameRng};
+use systems::{
+    attack_system::AttackSystem,
+    interact_system::InteractSystem,
+    item_system::{ItemAction, ItemSystem},
+    level_system::LevelSystem,
+    movement_system::MovementSystem,
+    path_system::PathSystem,
+    snapshot_system::SnapshotSystem,
+    stairs_system::StairsSystem,
+    Command, CommandSystem,
+};
 
 //ECS Modules
 pub mod components;
@@ -18,39 +38,685 @@ pub mod resources;
 mod systems;
 mod entities;
 
+//How long recover() waits for the GameLog before giving up on showing the error.
+const RECOVER_TIMEOUT: Duration = Duration::from_millis(100);
+
 pub struct GameWorld {
-    channel: (Receiver<MutateCommand>, SyncSender<DeltaNotification>),
+    channel: (Receiver<ModelEvent>, ViewSender<DeltaNotification>),
+    control_tx: Sender<ControlRequest>, //Asks the Controller for a change of RunState.
+    runstate: RunState,                 //As last sent by the Controller.
     ecs_ap: Arc<ECSAccessPoint>,
+    snapshots: Arc<SnapshotSlot<RenderSnapshot>>, //Read by the TUI thread, lock-free.
+    player: Entity,                               //Spawned anew for each game.
+    turn: u64,
 }
 
 impl GameWorld {
     pub fn new(
-        rx: Receiver<MutateCommand>,
-        tx: SyncSender<DeltaNotification>,
+        rx: Receiver<ModelEvent>,
+        tx: ViewSender<DeltaNotification>,
+        control_tx: Sender<ControlRequest>,
         ecs_ap: Arc<ECSAccessPoint>,
+        snapshots: Arc<SnapshotSlot<RenderSnapshot>>,
     ) -> Self {
-        GameWorld {
+        //Tracking starts before anything is spawned, so the first tick's
+        //DeltaNotifications include the spawning.
+        components::track_all_components(&ecs_ap);
+
+        let player = spawn_player(&ecs_ap);
+        ecs_ap.write_resource::<GameLog>().push("Welcome to GoblinRL!");
+        ecs_ap.maintain();
+
+        let gw = GameWorld {
             channel: (rx, tx),
+            control_tx,
+            runstate: RunState::MainMenu,
             ecs_ap,
+            snapshots,
+            player,
+            turn: 0,
+        };
+
+        gw.publish_snapshot(); //So the TUI has something to draw before the first tick.
+        gw
+    }
+
+    //Everything from any earlier run is thrown away, the player included.
+    //The level itself is built when the Controller goes on to MapGeneration.
+    fn new_game(&mut self, seed: u64) -> Result<(), Gremlin> {
+        let everything: Vec<Entity> = {
+            let entities = self.ecs_ap.read_resource::<EntitiesRes>();
+            (&*entities).join().collect()
+        };
+        for entity in everything {
+            self.ecs_ap.delete_entity(entity)?;
         }
+        self.ecs_ap.maintain();
+
+        {
+            let access = self.ecs_ap.req_access_set(
+                AccessSet::new()
+                    .write_resource::<Depth>()
+                    .write_resource::<GameRng>()
+                    .write_resource::<GameLog>(),
+            );
+            *access.write_resource::<Depth>() = Depth(1);
+            *access.write_resource::<GameRng>() = GameRng::seeded(seed);
+            *access.write_resource::<GameLog>() = GameLog::new();
+        }
+        self.turn = 0;
+
+        self.player = spawn_player(&self.ecs_ap);
+        self.ecs_ap
+            .write_resource::<GameLog>()
+            .push(format!("Welcome to GoblinRL! Seed: {}", seed));
+        self.ecs_ap.maintain();
+
+        for delta in self.ecs_ap.collect_deltas() {
+            self.channel.1.send(delta)?;
+        }
+        self.publish_snapshot();
+        Ok(())
     }
 
-    pub fn tick(&mut self) -> Result<Ticker, Gremlin> {
-        //println!("GW thread calling recv()...\r");
-        let cmd = self.channel.0.recv()?;
-        // println!("{:?}\r", cmd); //FOR TESTING ONLY
+    //Only MapGeneration is any work for the Model; every other state is only noted.
+    fn enter(&mut self, state: RunState) -> Result<(), Gremlin> {
+        if state == RunState::MapGeneration {
+            self.ecs_ap.run_system(&mut LevelSystem {});
+            self.ecs_ap.maintain();
 
-        match cmd {
-            MutateCommand::Test => {
-                //println!("Test Successful! You just hit Enter/Return.\r");
-                self.ecs_ap.print_map();
+            for delta in self.ecs_ap.collect_deltas() {
+                self.channel.1.send(delta)?;
             }
+            self.publish_snapshot();
+        }
+
+        self.runstate = state;
+        Ok(())
+    }
+
+    fn handle_command(&mut self, request: CommandRequest) -> Result<Ticker, Gremlin> {
+        let CommandRequest { command, reply_to } = request;
+        let changes_level = matches!(command, MutateCommand::Descend | MutateCommand::Ascend);
+
+        let outcome = match command {
             MutateCommand::Exit => {
+                if let Some(reply_to) = reply_to {
+                    let _ = reply_to.send(CommandReply {
+                        outcome: CommandOutcome::Accepted,
+                        deltas: Vec::new(),
+                    });
+                }
                 return Ok(Ticker::ExitProgram);
             }
-            _ => {}
+            command => self.carry_out(command),
         };
 
+        //A rejected command changes nothing but the log.
+        let outcome = match outcome {
+            Ok(()) => CommandOutcome::Accepted,
+            Err(rejection) => {
+                self.ecs_ap.write_resource::<GameLog>().push(rejection.to_string());
+                CommandOutcome::Rejected(rejection)
+            }
+        };
+
+        //Entities deleted during this tick are only actually removed here.
+        self.ecs_ap.maintain();
+
+        let accepted = outcome == CommandOutcome::Accepted;
+        let deltas = self.ecs_ap.collect_deltas();
+        match reply_to {
+            //If whoever asked is gone, nobody is left to care about the reply.
+            Some(reply_to) => {
+                let _ = reply_to.send(CommandReply { outcome, deltas });
+            }
+            None => {
+                for delta in deltas {
+                    self.channel.1.send(delta)?;
+                }
+            }
+        }
+
+        self.turn += 1;
+        self.publish_snapshot();
+
+        //The Controller waits in RunState::GameWorld until told how the turn ended.
+        let next = if !self.player_alive() {
+            RunState::GameOver
+        } else if changes_level && accepted {
+            RunState::NextLevel
+        } else {
+            RunState::awaiting_input_after(RunState::GameWorld)
+        };
+        self.ask(ControlRequest::Transition(next))?;
+
         Ok(Ticker::Continue)
     }
+
+    fn ask(&self, request: ControlRequest) -> Result<(), Gremlin> {
+        channel_trace::record(&request);
+        self.control_tx.send(request)?;
+        Ok(())
+    }
+
+    fn carry_out(&mut self, command: MutateCommand) -> Result<(), Rejection> {
+        match command {
+            MutateCommand::Exit => Ok(()), //Seen to by handle_command(), before getting here.
+            MutateCommand::Move(dir) => self.run_command(MovementSystem::new(dir)),
+            MutateCommand::Run(dir) => self.run(dir),
+            MutateCommand::Attack(dir) => self.run_command(AttackSystem::new(dir)),
+            MutateCommand::Wait => Ok(()),
+            MutateCommand::PickUp => self.run_command(ItemSystem::new(ItemAction::PickUp)),
+            MutateCommand::Drop(item) => self.run_command(ItemSystem::new(ItemAction::Drop(item))),
+            MutateCommand::Use(item, target) => self.run_command(ItemSystem::new(ItemAction::Use(item, target))),
+            MutateCommand::Descend => self.run_command(StairsSystem::descend()),
+            MutateCommand::Ascend => self.run_command(StairsSystem::ascend()),
+            MutateCommand::Interact(dir) => self.run_command(InteractSystem::new(dir)),
+            MutateCommand::Travel(to) => self.travel(to),
+            MutateCommand::Repeat(times, command) => self.repeat(times, *command),
+        }
+    }
+
+    //As travel(), with the same command each turn in place of each step.
+    //Only what a count prefix can repeat is repeated, so never a change of level.
+    fn repeat(&mut self, times: u16, command: MutateCommand) -> Result<(), Rejection> {
+        let repeatable = matches!(
+            command,
+            MutateCommand::Move(_) | MutateCommand::Run(_) | MutateCommand::Attack(_) | MutateCommand::Wait
+        );
+        if times == 0 || !repeatable {
+            return Err(Rejection::CannotRepeat);
+        }
+
+        for done in 0..times as usize {
+            if done > 0 {
+                self.turn += 1;
+                self.publish_snapshot();
+            }
+            if let Err(rejection) = self.carry_out(command.clone()) {
+                return self.refused(done, rejection);
+            }
+        }
+        Ok(())
+    }
+
+    //Every step but the last is its own turn here; handle_command() ends the last one.
+    fn travel(&mut self, to: Coords) -> Result<(), Rejection> {
+        let mut planner = Command::new(PathSystem::to(to));
+        self.ecs_ap.run_system(&mut planner);
+        planner.outcome?;
+
+        for (taken, dir) in planner.system.path.into_iter().enumerate() {
+            if taken > 0 {
+                self.turn += 1;
+                self.publish_snapshot();
+            }
+            if let Err(rejection) = self.run_command(MovementSystem::new(dir)) {
+                return self.refused(taken, rejection);
+            }
+        }
+        Ok(())
+    }
+
+    //Only a refused first step rejects a command of many steps. A later one ends it,
+    //accepted, with the refusal only logged; the turn begun for that step is taken back.
+    fn refused(&mut self, taken: usize, rejection: Rejection) -> Result<(), Rejection> {
+        if taken == 0 {
+            return Err(rejection);
+        }
+        self.turn -= 1;
+        self.ecs_ap.write_resource::<GameLog>().push(rejection.to_string());
+        Ok(())
+    }
+
+    //As travel(), until next to something. Running into a wall is how a run usually ends,
+    //so a refusal after the first step is not even logged.
+    fn run(&mut self, dir: Dir) -> Result<(), Rejection> {
+        self.run_command(MovementSystem::new(dir))?;
+
+        while !self.something_nearby() {
+            self.turn += 1;
+            self.publish_snapshot();
+            if self.run_command(MovementSystem::new(dir)).is_err() {
+                self.turn -= 1; //The step was never taken, so handle_command() counts the last one.
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    //Anything with a Position, on or next to the player's tile, is worth stopping a run for.
+    fn something_nearby(&self) -> bool {
+        let access = self.ecs_ap.req_access_set(AccessSet::new().read::<Player>().read::<Position>());
+        let (players, positions) = (access.read_storage::<Player>(), access.read_storage::<Position>());
+        let at = match (&players, &positions).join().next() {
+            Some((_, position)) => position.0,
+            None => return true,
+        };
+
+        (&positions, !&players)
+            .join()
+            .any(|(position, _)| position.0.x.abs_diff(at.x) <= 1 && position.0.y.abs_diff(at.y) <= 1)
+    }
+
+    fn player_alive(&self) -> bool {
+        self.ecs_ap.is_alive(self.player)
+    }
+
+    fn run_command<S>(&self, system: S) -> Result<(), Rejection>
+    where
+        S: for<'a> CommandSystem<'a>,
+    {
+        let mut command = Command::new(system);
+        self.ecs_ap.run_system(&mut command);
+        command.outcome
+    }
+
+    fn publish_snapshot(&self) {
+        let mut ss = SnapshotSystem::new(self.turn);
+        self.ecs_ap.run_system(&mut ss);
+        self.snapshots.publish(ss.snapshot);
+    }
+}
+
+impl TickLoop for GameWorld {
+    fn tick(&mut self) -> Result<Ticker, Gremlin> {
+        match self.channel.0.recv()? {
+            ModelEvent::Command(request) => self.handle_command(request),
+            ModelEvent::RunState(state) => {
+                self.enter(state)?;
+                Ok(Ticker::Continue)
+            }
+            ModelEvent::NewGame { seed } => {
+                self.new_game(seed)?;
+                Ok(Ticker::Continue)
+            }
+        }
+    }
+
+    //Shown to the player like any other message. If the GameLog itself cannot
+    //be had, e.g. after an AccessTimeout, the error is only in the error log.
+    fn recover(&mut self, e: &Gremlin) {
+        if let Ok(mut log) = self.ecs_ap.write_resource_timeout::<GameLog>(RECOVER_TIMEOUT) {
+            log.push(format!("Something went wrong: {}", e));
+        }
+    }
+
+    fn fail(&mut self, e: Gremlin) {
+        //If the Controller is gone too, everything is shutting down already.
+        let _ = self.ask(ControlRequest::Shutdown(format!("The gameworld thread stopped: {}", e)));
+    }
+}
+
+//At the current Map's spawnpoint.
+fn spawn_player(ecs_ap: &ECSAccessPoint) -> Entity {
+    let spawn_at = {
+        let map = ecs_ap.read_resource::<Map>();
+        map.idx_to_coords(map.player_spawnpoint as u32)
+            .expect("Map's player_spawnpoint found to be out of bounds")
+    };
+    entities::build_player_entity(ecs_ap, spawn_at)
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::sync::mpsc::{self, Sender, SyncSender};
+
+    use specs::{Join, WorldExt};
+
+    use super::*;
+    use crate::common::{view_channel, Target, ViewEvent};
+    use components::{Aimed, Carried, Consumable, Door, Item, Name, Renderable, Stairs};
+    use resources::Depth;
+
+    //Stand in for the TUI's end of the view channel, and the Controller's end of
+    //the state channel; sending fails once either is dropped.
+    type View = (Sender<ViewEvent>, Receiver<ViewEvent>);
+    type Requests = Receiver<ControlRequest>;
+
+    fn test_gw() -> (GameWorld, SyncSender<ModelEvent>, Arc<ECSAccessPoint>, View, Requests) {
+        let mut ecs: specs::World = WorldExt::new();
+        resources::insert_all_resources(&mut ecs);
+        components::register_all_components(&mut ecs);
+        let ecs_ap = Arc::new(ECSAccessPoint::new(ecs));
+
+        let (mutate_tx, mutate_rx) = mpsc::sync_channel(1);
+        let view = view_channel();
+        let delta_tx = ViewSender::new(view.0.clone());
+        let (control_tx, requests) = mpsc::channel();
+        let gw = GameWorld::new(mutate_rx, delta_tx, control_tx, ecs_ap.clone(), Arc::new(SnapshotSlot::new()));
+
+        (gw, mutate_tx, ecs_ap, view, requests)
+    }
+
+    fn command(gw: &mut GameWorld, tx: &SyncSender<ModelEvent>, cmd: MutateCommand) {
+        tx.send(cmd.into()).unwrap();
+        assert_eq!(gw.tick().unwrap(), Ticker::Continue);
+    }
+
+    fn player_at(ecs_ap: &ECSAccessPoint) -> Coords {
+        let access = ecs_ap.req_access_set(AccessSet::new().read::<Player>().read::<Position>());
+        let (players, positions) = (access.read_storage::<Player>(), access.read_storage::<Position>());
+        (&players, &positions).join().next().unwrap().1 .0
+    }
+
+    fn last_log(ecs_ap: &ECSAccessPoint) -> String {
+        ecs_ap.read_resource::<GameLog>().last(1)[0].clone()
+    }
+
+    #[test]
+    fn test_move() {
+        let (mut gw, tx, ecs_ap, _view, _requests) = test_gw();
+        let spawn = player_at(&ecs_ap);
+
+        command(&mut gw, &tx, MutateCommand::Move(Dir::SE));
+        assert_eq!(player_at(&ecs_ap), Coords::new(spawn.x + 1, spawn.y + 1));
+
+        //empty_10x10 is walled in, so walking north eventually hits a wall.
+        for _ in 0..10 {
+            command(&mut gw, &tx, MutateCommand::Move(Dir::N));
+        }
+        assert_eq!(player_at(&ecs_ap), Coords::new(spawn.x + 1, 1));
+        assert_eq!(last_log(&ecs_ap), Rejection::Wall.to_string());
+    }
+
+    #[test]
+    fn test_items() {
+        let (mut gw, tx, ecs_ap, _view, _requests) = test_gw();
+        let at = player_at(&ecs_ap);
+
+        command(&mut gw, &tx, MutateCommand::PickUp);
+        assert_eq!(last_log(&ecs_ap), Rejection::NothingToPickUp.to_string());
+
+        let potion = ecs_ap
+            .create_entity()
+            .with(Item {})
+            .with(Consumable {})
+            .with(Name("potion".to_string()))
+            .with(Position(at))
+            .build();
+
+        command(&mut gw, &tx, MutateCommand::PickUp);
+        assert!(ecs_ap.read::<Carried>().contains(potion));
+        assert!(!ecs_ap.read::<Position>().contains(potion));
+
+        command(&mut gw, &tx, MutateCommand::Drop(potion));
+        assert_eq!(ecs_ap.read::<Position>().get(potion), Some(&Position(at)));
+        command(&mut gw, &tx, MutateCommand::Use(potion, None));
+        assert_eq!(last_log(&ecs_ap), Rejection::NotCarried(potion).to_string());
+
+        command(&mut gw, &tx, MutateCommand::PickUp);
+        command(&mut gw, &tx, MutateCommand::Use(potion, Some(Target::Tile(at))));
+        assert_eq!(last_log(&ecs_ap), Rejection::NotAimed(potion).to_string());
+
+        command(&mut gw, &tx, MutateCommand::Use(potion, None));
+        assert_eq!(last_log(&ecs_ap), "You use the potion.");
+        assert!(!ecs_ap.is_alive(potion));
+    }
+
+    #[test]
+    fn test_aimed_items() {
+        let (mut gw, tx, ecs_ap, _view, _requests) = test_gw();
+        let at = player_at(&ecs_ap);
+        let player = gw.player;
+
+        let scroll = |ecs_ap: &ECSAccessPoint| {
+            ecs_ap
+                .create_entity()
+                .with(Item {})
+                .with(Consumable {})
+                .with(Aimed {})
+                .with(Name("scroll".to_string()))
+                .with(Carried { by: player })
+                .build()
+        };
+        let goblin = ecs_ap
+            .create_entity()
+            .with(Name("goblin".to_string()))
+            .with(Position(Coords::new(at.x + 1, at.y)))
+            .build();
+
+        let first = scroll(&ecs_ap);
+        command(&mut gw, &tx, MutateCommand::Use(first, None));
+        assert_eq!(last_log(&ecs_ap), Rejection::NeedsTarget(first).to_string());
+        command(&mut gw, &tx, MutateCommand::Use(first, Some(Target::Tile(Coords::new(99u16, 99u16)))));
+        assert_eq!(last_log(&ecs_ap), Rejection::InvalidTarget.to_string());
+        assert!(ecs_ap.is_alive(first));
+
+        command(&mut gw, &tx, MutateCommand::Use(first, Some(Target::Entity(goblin))));
+        assert_eq!(last_log(&ecs_ap), "You use the scroll on the goblin.");
+        assert!(!ecs_ap.is_alive(first));
+
+        let second = scroll(&ecs_ap);
+        command(&mut gw, &tx, MutateCommand::Use(second, Some(Target::Tile(at))));
+        assert_eq!(last_log(&ecs_ap), format!("You use the scroll at {}, {}.", at.x, at.y));
+    }
+
+    #[test]
+    fn test_doors_and_stairs() {
+        let (mut gw, tx, ecs_ap, _view, _requests) = test_gw();
+        let at = player_at(&ecs_ap);
+        let east = Coords::new(at.x + 1, at.y);
+
+        let door = ecs_ap
+            .create_entity()
+            .with(Door { open: false })
+            .with(Renderable { glyph: '+' })
+            .with(Position(east))
+            .build();
+
+        command(&mut gw, &tx, MutateCommand::Move(Dir::E));
+        assert_eq!(last_log(&ecs_ap), Rejection::Blocked.to_string());
+        command(&mut gw, &tx, MutateCommand::Interact(Dir::W));
+        assert_eq!(last_log(&ecs_ap), Rejection::NothingToInteractWith.to_string());
+
+        command(&mut gw, &tx, MutateCommand::Interact(Dir::E));
+        assert!(ecs_ap.read::<Door>().get(door).unwrap().open);
+        command(&mut gw, &tx, MutateCommand::Move(Dir::E));
+        assert_eq!(player_at(&ecs_ap), east);
+
+        command(&mut gw, &tx, MutateCommand::Descend);
+        assert_eq!(last_log(&ecs_ap), Rejection::NoStairsDown.to_string());
+
+        ecs_ap.create_entity().with(Stairs::Down).with(Position(east)).build();
+        command(&mut gw, &tx, MutateCommand::Descend);
+        assert_eq!(*ecs_ap.read_resource::<Depth>(), Depth(2));
+        command(&mut gw, &tx, MutateCommand::Ascend);
+        assert_eq!(last_log(&ecs_ap), Rejection::NoStairsUp.to_string());
+    }
+
+    #[test]
+    fn test_travel() {
+        let (mut gw, tx, ecs_ap, _view, _requests) = test_gw();
+        let spawn = player_at(&ecs_ap);
+        let around = Coords::new(spawn.x + 3, spawn.y);
+
+        //A closed door is walked around, not through, and each step is a turn.
+        ecs_ap
+            .create_entity()
+            .with(Door { open: false })
+            .with(Position(Coords::new(spawn.x + 1, spawn.y)))
+            .build();
+        command(&mut gw, &tx, MutateCommand::Travel(around));
+        assert_eq!(player_at(&ecs_ap), around);
+        assert_eq!(gw.turn, 3);
+
+        command(&mut gw, &tx, MutateCommand::Travel(Coords::new(0u16, 0u16)));
+        assert_eq!(last_log(&ecs_ap), Rejection::NoPath.to_string());
+        command(&mut gw, &tx, MutateCommand::Travel(Coords::new(99u16, 0u16)));
+        assert_eq!(last_log(&ecs_ap), Rejection::OutOfBounds.to_string());
+        assert_eq!(player_at(&ecs_ap), around);
+    }
+
+    #[test]
+    fn test_run_and_attack() {
+        let (mut gw, tx, ecs_ap, _view, _requests) = test_gw();
+        let spawn = player_at(&ecs_ap);
+
+        //Runs until the wall refuses a step, with a turn for each step taken.
+        command(&mut gw, &tx, MutateCommand::Run(Dir::N));
+        assert_eq!(player_at(&ecs_ap), Coords::new(spawn.x, 1));
+        assert_eq!(gw.turn, spawn.y as u64 - 1);
+        command(&mut gw, &tx, MutateCommand::Run(Dir::N));
+        assert_eq!(last_log(&ecs_ap), Rejection::Wall.to_string());
+
+        //Stops next to anything, here a potion two tiles short of the far wall.
+        ecs_ap
+            .create_entity()
+            .with(Item {})
+            .with(Position(Coords::new(6u16, 1u16)))
+            .build();
+        command(&mut gw, &tx, MutateCommand::Run(Dir::E));
+        assert_eq!(player_at(&ecs_ap), Coords::new(5u16, 1u16));
+
+        command(&mut gw, &tx, MutateCommand::Attack(Dir::E));
+        assert_eq!(last_log(&ecs_ap), Rejection::NothingToAttack.to_string());
+        ecs_ap
+            .create_entity()
+            .with(Name("goblin".to_string()))
+            .with(Position(Coords::new(5u16, 2u16)))
+            .build();
+        command(&mut gw, &tx, MutateCommand::Attack(Dir::S));
+        assert_eq!(last_log(&ecs_ap), "You hit the goblin.");
+        assert_eq!(player_at(&ecs_ap), Coords::new(5u16, 1u16));
+    }
+
+    #[test]
+    fn test_repeat() {
+        let (mut gw, tx, ecs_ap, view, _requests) = test_gw();
+        let reply_tx: ViewSender<CommandReply> = ViewSender::new(view.0.clone());
+        let spawn = player_at(&ecs_ap);
+
+        command(&mut gw, &tx, MutateCommand::Repeat(2, Box::new(MutateCommand::Move(Dir::S))));
+        assert_eq!(player_at(&ecs_ap), Coords::new(spawn.x, spawn.y + 2));
+        assert_eq!(gw.turn, 2);
+
+        //Stops at the first refusal, here the wall after two more tiles. Having moved,
+        //it is still accepted, and the refused step takes no turn.
+        view.1.try_iter().for_each(drop);
+        let repeat = MutateCommand::Repeat(9, Box::new(MutateCommand::Move(Dir::S)));
+        tx.send(ModelEvent::from(CommandRequest::with_reply(repeat, reply_tx))).unwrap();
+        gw.tick().unwrap();
+        assert!(matches!(
+            view.1.try_recv(),
+            Ok(ViewEvent::Reply(CommandReply { outcome: CommandOutcome::Accepted, .. }))
+        ));
+        assert_eq!(player_at(&ecs_ap), Coords::new(spawn.x, 8));
+        assert_eq!(last_log(&ecs_ap), Rejection::Wall.to_string());
+        assert_eq!(gw.turn, 4);
+
+        command(&mut gw, &tx, MutateCommand::Repeat(0, Box::new(MutateCommand::Wait)));
+        assert_eq!(last_log(&ecs_ap), Rejection::CannotRepeat.to_string());
+        command(&mut gw, &tx, MutateCommand::Repeat(2, Box::new(MutateCommand::Descend)));
+        assert_eq!(last_log(&ecs_ap), Rejection::CannotRepeat.to_string());
+        assert_eq!(ecs_ap.read_resource::<Depth>().0, 1);
+    }
+
+    #[test]
+    fn test_command_reply() {
+        let (mut gw, tx, ecs_ap, view, _requests) = test_gw();
+        let reply_tx: ViewSender<CommandReply> = ViewSender::new(view.0.clone());
+        let spawn = player_at(&ecs_ap);
+
+        //Without a reply handle, the spawning done by GameWorld::new() arrives as DeltaNotifications.
+        command(&mut gw, &tx, MutateCommand::Wait);
+        assert_eq!(view.1.try_iter().filter(|e| matches!(e, ViewEvent::Delta(_))).count(), 2);
+
+        tx.send(ModelEvent::from(CommandRequest::with_reply(MutateCommand::Move(Dir::E), reply_tx.clone()))).unwrap();
+        gw.tick().unwrap();
+        let reply = match view.1.try_recv().unwrap() {
+            ViewEvent::Reply(reply) => reply,
+            other => panic!("expected a CommandReply, got {:?}", other),
+        };
+        assert_eq!(reply.outcome, CommandOutcome::Accepted);
+        assert!(matches!(
+            reply.deltas[..],
+            [DeltaNotification::EntityMoved { from, to, .. }] if from == spawn && to == Coords::new(spawn.x + 1, spawn.y)
+        ));
+        assert!(view.1.try_recv().is_err()); //The deltas were not also sent separately.
+
+        for _ in 0..10 {
+            tx.send(ModelEvent::from(CommandRequest::with_reply(MutateCommand::Move(Dir::E), reply_tx.clone()))).unwrap();
+            gw.tick().unwrap();
+        }
+        let reply = match view.1.try_iter().last().unwrap() {
+            ViewEvent::Reply(reply) => reply,
+            other => panic!("expected a CommandReply, got {:?}", other),
+        };
+        assert_eq!(reply.outcome, CommandOutcome::Rejected(Rejection::Wall));
+        assert!(reply.deltas.is_empty());
+
+        tx.send(ModelEvent::from(CommandRequest::with_reply(MutateCommand::Exit, reply_tx))).unwrap();
+        assert_eq!(gw.tick().unwrap(), Ticker::ExitProgram);
+        assert!(matches!(
+            view.1.try_recv(),
+            Ok(ViewEvent::Reply(CommandReply { outcome: CommandOutcome::Accepted, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_runstate() {
+        let (mut gw, tx, ecs_ap, view, requests) = test_gw();
+        let asked = || match requests.try_recv().unwrap() {
+            ControlRequest::Transition(next) => next,
+            other => panic!("expected a Transition, got {:?}", other),
+        };
+        command(&mut gw, &tx, MutateCommand::Move(Dir::E));
+        assert_eq!(asked(), RunState::awaiting_input_after(RunState::GameWorld));
+
+        //Only an accepted Descend or Ascend leads to the next level.
+        command(&mut gw, &tx, MutateCommand::Descend);
+        assert!(asked().is(&RunState::awaiting_input_after(RunState::GameWorld)));
+
+        let east = player_at(&ecs_ap);
+        let stairs = ecs_ap.create_entity().with(Stairs::Down).with(Position(east)).build();
+        command(&mut gw, &tx, MutateCommand::Descend);
+        assert_eq!(asked(), RunState::NextLevel);
+
+        //The Controller then sends MapGeneration, and the new level is built at once.
+        view.1.try_iter().for_each(drop);
+        tx.send(ModelEvent::RunState(RunState::MapGeneration)).unwrap();
+        assert_eq!(gw.tick().unwrap(), Ticker::Continue);
+        assert_eq!(gw.runstate, RunState::MapGeneration);
+        let spawn = {
+            let map = ecs_ap.read_resource::<Map>();
+            map.idx_to_coords(map.player_spawnpoint as u32).unwrap()
+        };
+        assert_eq!(player_at(&ecs_ap), spawn);
+        assert!(!ecs_ap.is_alive(stairs));
+        assert!(view.1.try_iter().any(|e| matches!(e, ViewEvent::Delta(DeltaNotification::EntityMoved { .. }))));
+        assert!(requests.try_recv().is_err()); //Entering a state asks for no other.
+
+        //Nothing can kill the player yet, but once it is gone, the game is over.
+        ecs_ap.delete_entity(gw.player).unwrap();
+        command(&mut gw, &tx, MutateCommand::Wait);
+        assert_eq!(asked(), RunState::GameOver);
+    }
+
+    #[test]
+    fn test_new_game() {
+        let (mut gw, tx, ecs_ap, _view, _requests) = test_gw();
+        let spawn = player_at(&ecs_ap);
+        let old_player = {
+            let access = ecs_ap.req_access_set(AccessSet::new().read::<Player>().read_resource::<EntitiesRes>());
+            let (entities, players) = (access.read_resource::<EntitiesRes>(), access.read_storage::<Player>());
+            (&*entities, &players).join().next().unwrap().0
+        };
+
+        command(&mut gw, &tx, MutateCommand::Move(Dir::E));
+        let potion = ecs_ap.create_entity().with(Item {}).with(Position(spawn)).build();
+        *ecs_ap.write_resource::<Depth>() = Depth(3);
+
+        tx.send(ModelEvent::NewGame { seed: 7 }).unwrap();
+        assert_eq!(gw.tick().unwrap(), Ticker::Continue);
+
+        assert!(!ecs_ap.is_alive(old_player));
+        assert!(!ecs_ap.is_alive(potion));
+        assert_eq!(player_at(&ecs_ap), spawn);
+        assert_eq!(*ecs_ap.read_resource::<Depth>(), Depth(1));
+        assert_eq!(last_log(&ecs_ap), "Welcome to GoblinRL! Seed: 7");
+        assert_eq!(gw.turn, 0);
+    }
 }
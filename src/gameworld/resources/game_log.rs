@@ -0,0 +1,32 @@
+//Jerome M. St.Martin
+//June 23, 2022
+
+//-----------------------------------------------------------------------------
+//----------------------- Messages Shown to the Player ------------------------
+//-----------------------------------------------------------------------------
+
+//Older entries are dropped once there are more than this.
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Default, Debug)]
+pub struct GameLog {
+    entries: Vec<String>,
+}
+
+impl GameLog {
+    pub fn new() -> Self {
+        GameLog::default()
+    }
+
+    pub fn push<S: Into<String>>(&mut self, entry: S) {
+        self.entries.push(entry.into());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    ///Up to the last n entries, oldest first.
+    pub fn last(&self, n: usize) -> &[String] {
+        &self.entries[self.entries.len().saturating_sub(n)..]
+    }
+}
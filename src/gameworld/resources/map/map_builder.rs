@@ -5,12 +5,17 @@
 //---------------------------- Map Builder Pattern ----------------------------
 //-----------------------------------------------------------------------------
 
+use rand::Rng;
+
 use super::Map;
 use super::precon::*;
 
+//How much of the inside of a procgen map is carved out into floor.
+const PROCGEN_FLOOR_PERCENT: usize = 45;
+
 pub struct MapBuilder {
     size: Option<u16>,
-    layout: Option<&'static str>,
+    layout: Option<String>,
 }
 
 impl MapBuilder {
@@ -22,20 +27,46 @@ impl MapBuilder {
     }
 
     pub fn with_precon_layout(mut self, precon: PreCon) -> Self {
-        self.layout = Some(precon.layout);
+        self.layout = Some(precon.layout.to_string());
         self.size = Some(precon.size);
         self
     }
-/*
-    pub fn with_procgen_layout(mut self) -> Self {
-        //TODO
+
+    ///Carves a cave out of solid rock by a random walk from the middle, so every
+    ///floor tile can be reached from the spawnpoint there. The same rng state
+    ///always carves the same cave.
+    pub fn with_procgen_layout<R: Rng>(mut self, size: u16, rng: &mut R) -> Self {
+        let side = size as usize;
+        let mut tiles = vec!['#'; side * side];
+        let wanted = (side - 2).pow(2) * PROCGEN_FLOOR_PERCENT / 100;
+
+        let (mut x, mut y) = (side / 2, side / 2);
+        let mut carved = 0;
+        while carved < wanted {
+            if tiles[y * side + x] == '#' {
+                tiles[y * side + x] = '.';
+                carved += 1;
+            }
+            //Never onto the outermost ring, which stays wall.
+            match rng.gen_range(0..4) {
+                0 if y > 1 => y -= 1,
+                1 if x < side - 2 => x += 1,
+                2 if y < side - 2 => y += 1,
+                3 if x > 1 => x -= 1,
+                _ => {}
+            }
+        }
+        tiles[(side / 2) * side + side / 2] = '@';
+
+        self.layout = Some(tiles.into_iter().collect());
+        self.size = Some(size);
         self
     }
-*/
+
     pub fn build(self) -> Map {
         let mut map = Map::new(self.size.unwrap());
         
-        for (idx, c) in str_to_no_whitespace_chars(self.layout.unwrap()).enumerate() {
+        for (idx, c) in str_to_no_whitespace_chars(&self.layout.unwrap()).enumerate() {
             match c {
                 '#' => {
                     map.walls[idx] = true;
@@ -54,6 +85,6 @@ impl MapBuilder {
     }
 }
 
-fn str_to_no_whitespace_chars(s: &'static str) -> impl Iterator<Item = char> {
+fn str_to_no_whitespace_chars(s: &str) -> impl Iterator<Item = char> + '_ {
     s.chars().filter(|c| *c != '\r' && *c != '\n' && *c != ' ')
 }
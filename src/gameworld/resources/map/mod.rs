@@ -25,11 +25,11 @@ mod map_builder;
 use usize as Index;
 
 pub struct Map {
-    pub dirty_flag: bool,
     pub size: u16,
     pub player_spawnpoint: Index,
     pub walls: Vec<bool>, //Must be initialized to have size^2 elements.
     pub blocked: Vec<bool>, //Must be initialized to have size^2 elements.
+    #[allow(dead_code)] //Not filled in yet.
     pub tile_contents: HashMap<Index, [Entity; 8]>,
 }
 
@@ -44,7 +44,6 @@ impl Map {
     pub fn new<T: Into<u16> + Copy>(size: T) -> Self
     {
         Map {
-            dirty_flag: false,
             size: size.into(),
             player_spawnpoint: 11,
             walls: vec![false; size.into().pow(2) as usize],
@@ -78,7 +77,7 @@ impl Map {
     /// tiles which are also walls, in order to determine which line-glyph
     /// to draw for the passed-in wall tile.
     pub fn prettify_wall(&self,
-                         walls_vec: &Vec<bool>,
+                         walls_vec: &[bool],
                          wall_coords: Coords) -> Result<char, Gremlin> {
 
         //Make sure passed-in wall_coords do in fact map to a wall.
@@ -140,7 +139,7 @@ impl Map {
             (false, false, false, false) => { '■' },
         };
 
-        return Ok(glyph)
+        Ok(glyph)
     }
 }
 
@@ -156,12 +155,12 @@ mod test {
     fn test_coords_to_idx() {
         let map = Map::new(10u16);
 
-        assert!(map.coords_to_idx(Coords::new(0u16, 1u16)).unwrap() == 10 as usize);
-        assert!(map.coords_to_idx(Coords::new(1u16, 1u16)).unwrap() == 11 as usize);
-        assert!(map.coords_to_idx(Coords::new(1u16, 0u16)).unwrap() == 1 as usize);
-        assert!(map.coords_to_idx(Coords::new(9u16, 0u16)).unwrap() == 9 as usize);
-        assert!(map.coords_to_idx(Coords::new(0u16, 9u16)).unwrap() == 90 as usize);
-        assert!(map.coords_to_idx(Coords::new(9u16, 9u16)).unwrap() == 99 as usize);
+        assert!(map.coords_to_idx(Coords::new(0u16, 1u16)).unwrap() == 10);
+        assert!(map.coords_to_idx(Coords::new(1u16, 1u16)).unwrap() == 11);
+        assert!(map.coords_to_idx(Coords::new(1u16, 0u16)).unwrap() == 1);
+        assert!(map.coords_to_idx(Coords::new(9u16, 0u16)).unwrap() == 9);
+        assert!(map.coords_to_idx(Coords::new(0u16, 9u16)).unwrap() == 90);
+        assert!(map.coords_to_idx(Coords::new(9u16, 9u16)).unwrap() == 99);
 
         assert!(map.coords_to_idx(Coords::new(10u16, 10u16)).is_err());
     }
@@ -179,4 +178,13 @@ mod test {
 
         assert!(map.idx_to_coords(100u16).is_err());
     }
+
+    #[test]
+    fn test_build_precon() {
+        let map = Map::builder().with_precon_layout(precon::test_3x3()).build();
+
+        assert_eq!(map.size, 3);
+        assert!(map.walls.iter().all(|&wall| wall));
+        assert!(map.blocked.iter().all(|&blocked| blocked));
+    }
 }
@@ -30,6 +30,7 @@ pub fn empty_10x10() -> PreCon {
     }
 }
 
+#[cfg(test)]
 pub fn test_3x3() -> PreCon {
     let map_str = "
     ###
@@ -5,14 +5,70 @@
 //--------------------------- ECS Resource Module -----------------------------
 //-----------------------------------------------------------------------------
 
+use rand::{rngs::StdRng, SeedableRng};
+
+pub(crate) mod game_log;
 pub(crate) mod map;
 
 pub(crate) fn insert_all_resources(ecs: &mut specs::World) {
     ecs.insert(generate_map());
+    ecs.insert(game_log::GameLog::new());
+    ecs.insert(Depth(1));
+    ecs.insert(GameRng::seeded(0)); //Replaced on each New Game, with the seed chosen.
+}
+
+///How many levels down the player is; the first level is 1.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Depth(pub u32);
+
+///All randomness in a run comes from here, so the same seed plays out the same way.
+#[derive(Debug)]
+pub struct GameRng(pub StdRng);
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        GameRng(StdRng::seed_from_u64(seed))
+    }
 }
 
-fn generate_map() -> map::Map {
+//One side of every level generate_level() builds.
+const LEVEL_SIZE: u16 = 16;
+
+///What the World starts out with, before any game has begun.
+pub(crate) fn generate_map() -> map::Map {
     map::Map::builder()
         .with_precon_layout(map::precon::empty_10x10())
         .build()
 }
+
+///A new cave at every Depth, carved with the run's GameRng.
+pub(crate) fn generate_level(rng: &mut GameRng) -> map::Map {
+    map::Map::builder()
+        .with_procgen_layout(LEVEL_SIZE, &mut rng.0)
+        .build()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_generate_level() {
+        let first = generate_level(&mut GameRng::seeded(42));
+        let again = generate_level(&mut GameRng::seeded(42));
+        let other = generate_level(&mut GameRng::seeded(43));
+
+        assert_eq!(first.size, LEVEL_SIZE);
+        assert_eq!(first.walls, again.walls);
+        assert_ne!(first.walls, other.walls);
+        assert!(!first.walls[first.player_spawnpoint]);
+
+        //The outermost ring is always wall.
+        let side = LEVEL_SIZE as usize;
+        for i in 0..side {
+            assert!(first.walls[i] && first.walls[(side - 1) * side + i]);
+            assert!(first.walls[i * side] && first.walls[i * side + side - 1]);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//Jerome M. St.Martin
+//July 2, 2022
+
+//-----------------------------------------------------------------------------
+//----------------- Carries out MutateCommand::Attack(Dir) --------------------
+//-----------------------------------------------------------------------------
+
+use specs::{Entities, Join, ReadExpect, ReadStorage, WriteExpect};
+
+use super::{find_player, CommandSystem};
+use crate::common::{Coords, Dir, Rejection};
+use crate::gameworld::components::{Door, Item, Name, Player, Position, Stairs};
+use crate::gameworld::resources::{game_log::GameLog, map::Map};
+
+///Strikes at the adjacent tile without ever stepping onto it. Anything there but
+///an Item or Stairs can be hit; nothing has health yet, so a hit is only logged.
+pub struct AttackSystem {
+    dir: Dir,
+}
+
+impl AttackSystem {
+    pub fn new(dir: Dir) -> Self {
+        AttackSystem { dir }
+    }
+}
+
+impl<'a> CommandSystem<'a> for AttackSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Door>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, Stairs>,
+    );
+
+    fn try_run(&mut self, data: Self::SystemData) -> Result<(), Rejection> {
+        let (entities, map, mut log, players, positions, names, doors, items, stairs) = data;
+
+        let (_, from) = find_player(&entities, &players, &positions)?;
+        let at = Coords::toward(from, self.dir, map.size).map_err(|_| Rejection::NothingToAttack)?;
+
+        let (target, _, _, _) = (&entities, &positions, !&items, !&stairs)
+            .join()
+            .find(|(_, position, _, _)| position.0 == at)
+            .ok_or(Rejection::NothingToAttack)?;
+
+        let called = match (names.get(target), doors.get(target)) {
+            (Some(name), _) => name.0.as_str(),
+            (None, Some(_)) => "door",
+            (None, None) => "thing",
+        };
+        log.push(format!("You hit the {}.", called));
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+//Jerome M. St.Martin
+//June 25, 2022
+
+//-----------------------------------------------------------------------------
+//---------------- Carries out MutateCommand::Interact(Dir) -------------------
+//-----------------------------------------------------------------------------
+
+use specs::{Entities, Join, ReadExpect, ReadStorage, WriteExpect, WriteStorage};
+
+use super::{find_player, CommandSystem};
+use crate::common::{Coords, Dir, Rejection};
+use crate::gameworld::components::{Door, Player, Position, Renderable};
+use crate::gameworld::resources::{game_log::GameLog, map::Map};
+
+const OPEN_DOOR_GLYPH: char = '\'';
+const CLOSED_DOOR_GLYPH: char = '+';
+
+///Doors are the only thing that can be interacted with, for now: they open and close.
+pub struct InteractSystem {
+    dir: Dir,
+}
+
+impl InteractSystem {
+    pub fn new(dir: Dir) -> Self {
+        InteractSystem { dir }
+    }
+}
+
+impl<'a> CommandSystem<'a> for InteractSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, Door>,
+        WriteStorage<'a, Renderable>,
+    );
+
+    fn try_run(&mut self, data: Self::SystemData) -> Result<(), Rejection> {
+        let (entities, map, mut log, players, positions, mut doors, mut renderables) = data;
+
+        let (_, from) = find_player(&entities, &players, &positions)?;
+        let at = Coords::toward(from, self.dir, map.size).map_err(|_| Rejection::NothingToInteractWith)?;
+
+        let (door_entity, door, _) = (&entities, &mut doors, &positions)
+            .join()
+            .find(|(_, _, position)| position.0 == at)
+            .ok_or(Rejection::NothingToInteractWith)?;
+
+        door.open = !door.open;
+        if let Some(renderable) = renderables.get_mut(door_entity) {
+            renderable.glyph = if door.open { OPEN_DOOR_GLYPH } else { CLOSED_DOOR_GLYPH };
+        }
+
+        log.push(if door.open { "You open the door." } else { "You close the door." });
+        Ok(())
+    }
+}
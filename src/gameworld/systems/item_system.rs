@@ -0,0 +1,110 @@
+//Jerome M. St.Martin
+//June 25, 2022
+
+//-----------------------------------------------------------------------------
+//----------- Carries out MutateCommand::PickUp, ::Drop, and ::Use ------------
+//-----------------------------------------------------------------------------
+
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, WriteExpect, WriteStorage};
+
+use super::{find_player, CommandSystem};
+use crate::common::{Rejection, Target};
+use crate::gameworld::components::{Aimed, Carried, Consumable, Item, Name, Player, Position};
+use crate::gameworld::resources::{game_log::GameLog, map::Map};
+
+pub enum ItemAction {
+    PickUp,
+    Drop(Entity),
+    Use(Entity, Option<Target>),
+}
+
+pub struct ItemSystem {
+    action: ItemAction,
+}
+
+impl ItemSystem {
+    pub fn new(action: ItemAction) -> Self {
+        ItemSystem { action }
+    }
+}
+
+impl<'a> CommandSystem<'a> for ItemSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, Consumable>,
+        ReadStorage<'a, Aimed>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Carried>,
+    );
+
+    fn try_run(&mut self, data: Self::SystemData) -> Result<(), Rejection> {
+        let (entities, map, mut log, players, items, consumables, aimed, names, mut positions, mut carried) = data;
+
+        let name_of = |item: Entity| -> String {
+            names.get(item).map_or_else(|| "item".to_string(), |name| name.0.clone())
+        };
+
+        let (player, at) = find_player(&entities, &players, &positions)?;
+
+        //Whatever is being dropped or used must be in the player's hands.
+        let carried_by_player = |item: Entity| -> Result<(), Rejection> {
+            match carried.get(item) {
+                Some(c) if c.by == player => Ok(()),
+                _ => Err(Rejection::NotCarried(item)),
+            }
+        };
+
+        match self.action {
+            ItemAction::PickUp => {
+                let item = (&entities, &items, &positions)
+                    .join()
+                    .find(|(_, _, position)| position.0 == at)
+                    .map(|(item, _, _)| item)
+                    .ok_or(Rejection::NothingToPickUp)?;
+
+                positions.remove(item);
+                let _ = carried.insert(item, Carried { by: player });
+                log.push(format!("You pick up the {}.", name_of(item)));
+            }
+
+            ItemAction::Drop(item) => {
+                carried_by_player(item)?;
+
+                carried.remove(item);
+                let _ = positions.insert(item, Position(at));
+                log.push(format!("You drop the {}.", name_of(item)));
+            }
+
+            ItemAction::Use(item, target) => {
+                carried_by_player(item)?;
+                if !consumables.contains(item) {
+                    return Err(Rejection::NotUsable(item));
+                }
+
+                let used = match (target, aimed.contains(item)) {
+                    (None, false) => format!("You use the {}.", name_of(item)),
+                    (None, true) => return Err(Rejection::NeedsTarget(item)),
+                    (Some(_), false) => return Err(Rejection::NotAimed(item)),
+                    (Some(Target::Entity(e)), true) if entities.is_alive(e) && positions.contains(e) => {
+                        format!("You use the {} on the {}.", name_of(item), name_of(e))
+                    }
+                    (Some(Target::Tile(coords)), true) if map.coords_to_idx(coords).is_ok() => {
+                        format!("You use the {} at {}, {}.", name_of(item), coords.x, coords.y)
+                    }
+                    (Some(_), true) => return Err(Rejection::InvalidTarget),
+                };
+
+                //Deleted for good by the end-of-tick maintain().
+                let _ = entities.delete(item);
+                log.push(used);
+            }
+        }
+
+        Ok(())
+    }
+}
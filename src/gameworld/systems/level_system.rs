@@ -0,0 +1,44 @@
+//Jerome M. St.Martin
+//June 28, 2022
+
+//-----------------------------------------------------------------------------
+//------------- Builds the Level at the Current Depth, on Entry ---------------
+//-----------------------------------------------------------------------------
+
+use specs::{Entities, Join, ReadStorage, System, WriteExpect, WriteStorage};
+
+use crate::gameworld::components::{Player, Position};
+use crate::gameworld::resources::{generate_level, map::Map, GameRng};
+
+///Run when the GameWorld enters RunState::MapGeneration. Replaces the Map with one drawn from the GameRng,
+///deletes everything lying on the old one, and puts the player on the spawnpoint.
+///Whatever the player carries has no Position, so it comes along.
+pub struct LevelSystem {}
+
+impl<'a> System<'a> for LevelSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, Map>,
+        WriteExpect<'a, GameRng>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, Position>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut map, mut rng, players, mut positions) = data;
+
+        *map = generate_level(&mut rng);
+        let spawn_at = map
+            .idx_to_coords(map.player_spawnpoint as u32)
+            .expect("Map's player_spawnpoint found to be out of bounds");
+
+        for (entity, position) in (&entities, &mut positions).join() {
+            if players.contains(entity) {
+                position.0 = spawn_at;
+            } else {
+                //Deleting can only fail for an entity that is already dead.
+                let _ = entities.delete(entity);
+            }
+        }
+    }
+}
@@ -5,3 +5,70 @@
 //---------------------------- ECS Systems Module -----------------------------
 //-----------------------------------------------------------------------------
 
+use std::ops::Deref;
+
+use specs::{storage::MaskedStorage, world::EntitiesRes, Entity, Join, ReadStorage, Storage, System, SystemData};
+
+use crate::common::{Coords, Rejection};
+use super::components::{Player, Position};
+
+pub(super) mod attack_system;
+pub(super) mod interact_system;
+pub(super) mod item_system;
+pub(super) mod level_system;
+pub(super) mod movement_system;
+pub(super) mod path_system;
+pub(super) mod snapshot_system;
+pub(super) mod stairs_system;
+
+///Implemented by every system which carries out a MutateCommand, in place of
+///specs' System; run through a Command, which keeps whatever try_run() returned.
+pub(super) trait CommandSystem<'a> {
+    type SystemData: SystemData<'a>;
+
+    fn try_run(&mut self, data: Self::SystemData) -> Result<(), Rejection>;
+}
+
+/* The one System every CommandSystem is run as:
+ * let mut command = Command::new(MovementSystem::new(dir));
+ * ecs_ap.run_system(&mut command);
+ * command.outcome?;
+ */
+pub(super) struct Command<S> {
+    pub(super) system: S,
+    pub(super) outcome: Result<(), Rejection>,
+}
+
+impl<S> Command<S> {
+    pub(super) fn new(system: S) -> Self {
+        Command {
+            system,
+            outcome: Ok(()),
+        }
+    }
+}
+
+impl<'a, S: CommandSystem<'a>> System<'a> for Command<S> {
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        self.outcome = self.system.try_run(data);
+    }
+}
+
+//Every MutateCommand acts on behalf of the player, from wherever the player is.
+//Works with both ReadStorage and WriteStorage of Position.
+fn find_player<D>(
+    entities: &EntitiesRes,
+    players: &ReadStorage<Player>,
+    positions: &Storage<Position, D>,
+) -> Result<(Entity, Coords), Rejection>
+where
+    D: Deref<Target = MaskedStorage<Position>>,
+{
+    (entities, players, positions)
+        .join()
+        .next()
+        .map(|(player, _, position)| (player, position.0))
+        .ok_or(Rejection::NoPlayer)
+}
@@ -0,0 +1,63 @@
+//Jerome M. St.Martin
+//June 25, 2022
+
+//-----------------------------------------------------------------------------
+//------------------ Carries out MutateCommand::Move(Dir) ---------------------
+//-----------------------------------------------------------------------------
+
+use specs::{Entities, Join, ReadStorage, WriteExpect, WriteStorage};
+
+use super::{find_player, CommandSystem};
+use crate::common::{Coords, Dir, Rejection};
+use crate::gameworld::components::{Door, Player, Position};
+use crate::gameworld::resources::map::Map;
+
+pub struct MovementSystem {
+    dir: Dir,
+}
+
+impl MovementSystem {
+    pub fn new(dir: Dir) -> Self {
+        MovementSystem { dir }
+    }
+}
+
+impl<'a> CommandSystem<'a> for MovementSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, Map>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Door>,
+        WriteStorage<'a, Position>,
+    );
+
+    fn try_run(&mut self, data: Self::SystemData) -> Result<(), Rejection> {
+        let (entities, mut map, players, doors, mut positions) = data;
+
+        let (player, from) = find_player(&entities, &players, &positions)?;
+        let to = Coords::toward(from, self.dir, map.size).map_err(|_| Rejection::OutOfBounds)?;
+        let to_idx = map.coords_to_idx(to).map_err(|_| Rejection::OutOfBounds)?;
+
+        let closed_door = (&doors, &positions)
+            .join()
+            .any(|(door, position)| !door.open && position.0 == to);
+
+        if map.walls[to_idx] {
+            return Err(Rejection::Wall);
+        }
+
+        if map.blocked[to_idx] || closed_door {
+            return Err(Rejection::Blocked);
+        }
+
+        //The Map's own record of where the player stands moves along with it.
+        let from_idx = map.coords_to_idx(from).map_err(|_| Rejection::OutOfBounds)?;
+        map.blocked[from_idx] = false;
+        map.blocked[to_idx] = true;
+
+        if let Some(position) = positions.get_mut(player) {
+            position.0 = to;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//Jerome M. St.Martin
+//July 1, 2022
+
+//-----------------------------------------------------------------------------
+//------------- Plans the Steps of MutateCommand::Travel(Coords) --------------
+//-----------------------------------------------------------------------------
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use specs::{Entities, Join, ReadExpect, ReadStorage};
+
+use super::{find_player, CommandSystem};
+use crate::common::{Coords, Dir, Rejection};
+use crate::gameworld::components::{Door, Player, Position};
+use crate::gameworld::resources::map::Map;
+
+/* Only plans; GameWorld then takes the steps one turn at a time, each through
+ * MovementSystem, stopping at the first one refused:
+ * let mut planner = Command::new(PathSystem::to(coords));
+ * ecs_ap.run_system(&mut planner);
+ * for dir in planner.system.path { ... }
+ */
+pub struct PathSystem {
+    to: Coords,
+    pub path: Vec<Dir>, //Empty if already there.
+}
+
+impl PathSystem {
+    pub fn to(to: Coords) -> Self {
+        PathSystem {
+            to,
+            path: Vec::new(),
+        }
+    }
+}
+
+impl<'a> CommandSystem<'a> for PathSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Door>,
+        ReadStorage<'a, Position>,
+    );
+
+    fn try_run(&mut self, data: Self::SystemData) -> Result<(), Rejection> {
+        let (entities, map, players, doors, positions) = data;
+
+        let (_, from) = find_player(&entities, &players, &positions)?;
+        map.coords_to_idx(self.to).map_err(|_| Rejection::OutOfBounds)?;
+        if from == self.to {
+            return Ok(());
+        }
+
+        //Closed doors are in the way; opening one is left to the player.
+        let closed_doors: HashSet<Coords> = (&doors, &positions)
+            .join()
+            .filter(|(door, _)| !door.open)
+            .map(|(_, position)| position.0)
+            .collect();
+        let passable = |coords: Coords| match map.coords_to_idx(coords) {
+            Ok(idx) => !map.walls[idx] && !map.blocked[idx] && !closed_doors.contains(&coords),
+            Err(_) => false,
+        };
+
+        //Breadth-first, so the first path found is a shortest one.
+        let mut came_from: HashMap<Coords, (Coords, Dir)> = HashMap::new();
+        let mut frontier = VecDeque::from([from]);
+        while let Some(at) = frontier.pop_front() {
+            if at == self.to {
+                break;
+            }
+            for dir in Dir::ALL {
+                let next = match Coords::toward(at, dir, map.size) {
+                    Ok(next) => next,
+                    Err(_) => continue,
+                };
+                if next != from && !came_from.contains_key(&next) && passable(next) {
+                    came_from.insert(next, (at, dir));
+                    frontier.push_back(next);
+                }
+            }
+        }
+
+        let mut at = self.to;
+        while at != from {
+            let (previous, dir) = came_from.get(&at).ok_or(Rejection::NoPath)?;
+            self.path.push(*dir);
+            at = *previous;
+        }
+        self.path.reverse();
+        Ok(())
+    }
+}
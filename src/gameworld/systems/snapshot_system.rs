@@ -0,0 +1,149 @@
+//Jerome M. St.Martin
+//June 23, 2022
+
+//-----------------------------------------------------------------------------
+//---------------------- Builds the TUI's RenderSnapshot ----------------------
+//-----------------------------------------------------------------------------
+
+use specs::{Entities, Join, ReadExpect, ReadStorage, System};
+
+use crate::common::{RenderSnapshot, SnapshotStats};
+use crate::gameworld::components::{Carried, Door, Item, Name, Player, Position, Renderable, Stairs};
+use crate::gameworld::resources::{game_log::GameLog, map::Map};
+
+//How many of the newest GameLog entries go into each snapshot.
+const LOG_LINES: usize = 5;
+
+/* Run once at the end of every tick, through ecs_ap.run_system(), after which
+ * the finished snapshot is taken out of the system and published:
+ * let mut ss = SnapshotSystem::new(turn);
+ * ecs_ap.run_system(&mut ss);
+ * slot.publish(ss.snapshot);
+ */
+pub struct SnapshotSystem {
+    pub snapshot: RenderSnapshot,
+}
+
+impl SnapshotSystem {
+    pub fn new(turn: u64) -> Self {
+        SnapshotSystem {
+            snapshot: RenderSnapshot {
+                turn,
+                ..RenderSnapshot::default()
+            },
+        }
+    }
+}
+
+impl<'a> System<'a> for SnapshotSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, GameLog>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Renderable>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Door>,
+        ReadStorage<'a, Stairs>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, Carried>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, map, log, players, positions, renderables, names, doors, stairs, items, carried) = data;
+        let snapshot = &mut self.snapshot;
+
+        //There is no field of view yet, so every tile is visible.
+        snapshot.map_size = map.size;
+        snapshot.tiles = (0..map.walls.len())
+            .map(|idx| {
+                let coords = map.idx_to_coords(idx as u32).expect("Map walls Vec found to be oversized");
+                map.prettify_wall(&map.walls, coords).unwrap_or('.')
+            })
+            .collect();
+
+        snapshot.entities = (&positions, &renderables)
+            .join()
+            .map(|(position, renderable)| (position.0, renderable.glyph))
+            .collect();
+
+        snapshot.names = (&entities, &positions, players.maybe(), names.maybe(), doors.maybe(), stairs.maybe(), items.maybe())
+            .join()
+            .filter_map(|(entity, position, player, name, door, stairs, item)| {
+                let called = match (player, name, door, stairs, item) {
+                    (Some(_), ..) => "you".to_string(),
+                    (_, Some(name), ..) => name.0.clone(),
+                    (_, _, Some(door), ..) if door.open => "an open door".to_string(),
+                    (_, _, Some(_), ..) => "a closed door".to_string(),
+                    (_, _, _, Some(Stairs::Down), _) => "stairs down".to_string(),
+                    (_, _, _, Some(Stairs::Up), _) => "stairs up".to_string(),
+                    (_, _, _, _, Some(_)) => "an item".to_string(),
+                    _ => return None,
+                };
+                Some((position.0, entity, called))
+            })
+            .collect();
+
+        let player = (&entities, &players).join().next().map(|(player, _)| player);
+        snapshot.inventory = (&entities, &carried, names.maybe())
+            .join()
+            .filter(|(_, carried, _)| Some(carried.by) == player)
+            .map(|(item, _, name)| (item, name.map_or_else(|| "item".to_string(), |name| name.0.clone())))
+            .collect();
+
+        snapshot.log = log.last(LOG_LINES).to_vec();
+
+        snapshot.stats = SnapshotStats {
+            player_at: (&positions, &players).join().next().map(|(position, _)| position.0),
+            entity_count: snapshot.entities.len(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use specs::WorldExt;
+
+    use super::*;
+    use crate::common::Coords;
+    use crate::ecs_access_point::ECSAccessPoint;
+    use crate::gameworld::{components, entities, resources};
+
+    #[test]
+    fn test_snapshot_contents() {
+        let mut ecs: specs::World = WorldExt::new();
+        resources::insert_all_resources(&mut ecs);
+        components::register_all_components(&mut ecs);
+        let ecs_ap = ECSAccessPoint::new(ecs);
+
+        let player = entities::build_player_entity(&ecs_ap, Coords::new(2u16, 3u16));
+        let potion = ecs_ap
+            .create_entity()
+            .with(components::Item {})
+            .with(components::Name("potion".to_string()))
+            .with(components::Carried { by: player })
+            .build();
+        for turn in 0..10 {
+            ecs_ap.write_resource::<GameLog>().push(format!("turn {}", turn));
+        }
+
+        let mut ss = SnapshotSystem::new(7);
+        ecs_ap.run_system(&mut ss);
+        let snapshot = ss.snapshot;
+
+        let map = ecs_ap.read_resource::<Map>();
+        assert_eq!(snapshot.turn, 7);
+        assert_eq!(snapshot.map_size, map.size);
+        assert_eq!(snapshot.tiles.len(), map.walls.len());
+        assert_eq!(snapshot.tiles[0], map.prettify_wall(&map.walls, Coords::new(0u16, 0u16)).unwrap_or('.'));
+        assert_eq!(snapshot.entities, vec![(Coords::new(2u16, 3u16), '@')]);
+        assert_eq!(snapshot.names, vec![(Coords::new(2u16, 3u16), player, "you".to_string())]);
+        assert_eq!(snapshot.inventory, vec![(potion, "potion".to_string())]);
+        assert_eq!(snapshot.log.len(), LOG_LINES);
+        assert_eq!(snapshot.log.last().unwrap(), "turn 9");
+        assert_eq!(snapshot.stats.player_at, Some(Coords::new(2u16, 3u16)));
+        assert_eq!(snapshot.stats.entity_count, 1);
+    }
+}
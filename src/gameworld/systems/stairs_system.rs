@@ -0,0 +1,64 @@
+//Jerome M. St.Martin
+//June 25, 2022
+
+//-----------------------------------------------------------------------------
+//------------- Carries out MutateCommand::Descend and ::Ascend ---------------
+//-----------------------------------------------------------------------------
+
+use specs::{Entities, Join, ReadStorage, WriteExpect};
+
+use super::{find_player, CommandSystem};
+use crate::common::Rejection;
+use crate::gameworld::components::{Player, Position, Stairs};
+use crate::gameworld::resources::{game_log::GameLog, Depth};
+
+///Only changes the Depth; building the level found there is up to map generation.
+pub struct StairsSystem {
+    down: bool,
+}
+
+impl StairsSystem {
+    pub fn descend() -> Self {
+        StairsSystem { down: true }
+    }
+
+    pub fn ascend() -> Self {
+        StairsSystem { down: false }
+    }
+}
+
+impl<'a> CommandSystem<'a> for StairsSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, Depth>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Stairs>,
+    );
+
+    fn try_run(&mut self, data: Self::SystemData) -> Result<(), Rejection> {
+        let (entities, mut depth, mut log, players, positions, stairs) = data;
+
+        let (_, at) = find_player(&entities, &players, &positions)?;
+        let wanted = if self.down { Stairs::Down } else { Stairs::Up };
+
+        let found = (&stairs, &positions)
+            .join()
+            .any(|(kind, position)| *kind == wanted && position.0 == at);
+
+        //The first level has no way further up, whatever stands there.
+        if !found || (!self.down && depth.0 <= 1) {
+            return Err(if self.down { Rejection::NoStairsDown } else { Rejection::NoStairsUp });
+        }
+
+        if self.down {
+            depth.0 += 1;
+            log.push(format!("You descend to depth {}.", depth.0));
+        } else {
+            depth.0 -= 1;
+            log.push(format!("You climb up to depth {}.", depth.0));
+        }
+        Ok(())
+    }
+}
@@ -6,19 +6,64 @@ use std::thread;
 
 use specs::WorldExt;
 
+mod channel_trace;
 mod common;
 mod controller;
 mod ecs_access_point;
 mod error;
 mod gameworld;
+mod options;
+mod terminal;
 mod tui;
 mod user_input;
 
 use ecs_access_point::ECSAccessPoint;
+use error::Gremlin;
 use gameworld::{components, resources};
+use options::Options;
 
 fn main() {
 
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(Gremlin::InvalidArgs(why)) => {
+            eprintln!("goblin_rl: {}", why);
+            std::process::exit(2);
+        }
+        Err(e) => panic!("{}", e),
+    };
+
+    //Viewing a trace replaces playing the game.
+    if let Some(filter) = options.view_trace {
+        if let Err(e) = channel_trace::viewer::replay(&filter, &mut std::io::stdout()) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    //Before the terminal is taken over, so that what was wrong can be read.
+    let keybindings = match user_input::Keybindings::find(options.keybindings.as_deref()) {
+        Ok(keybindings) => keybindings,
+        Err(Gremlin::InvalidKeybindings(why)) => {
+            eprintln!("goblin_rl: {}", why);
+            std::process::exit(2);
+        }
+        Err(e) => panic!("{}", e),
+    };
+
+    //The Controller keeps the Keybindings; the TUI only shows what they are.
+    let key_help = keybindings.help();
+
+    if let Some(path) = options.trace_channels {
+        channel_trace::start(path).unwrap(); //panics on failure, which is desired
+    }
+
+    //From here on, a panic on any thread gives the terminal back first.
+    terminal::install_panic_hook();
+    //Before the TUI thread starts drawing, so it draws on the alternate screen.
+    let terminal = terminal::TerminalGuard::enter().unwrap(); //panics on failure, which is desired
+
     // ECS Initialization
     let mut ecs_world: specs::World = WorldExt::new();
     resources::insert_all_resources(&mut ecs_world);
@@ -26,88 +71,75 @@ fn main() {
 
     let ecs_ap = Arc::new(ECSAccessPoint::new(ecs_world));
     let gw_ecs_ap = ecs_ap.clone();
-    let tui_ecs_ap = ecs_ap.clone();
+
+    //GameWorld publishes a RenderSnapshot each tick, which the TUI takes without locking.
+    let snapshots = Arc::new(common::SnapshotSlot::new());
+    let gw_snapshots = snapshots.clone();
 
     //Channel Initialization, endpoint names derived from the enums they send/recv.
-    let (mutate_tx, mutate_rx) = mpsc::sync_channel(1); // View --> Model
-    let (delta_tx, delta_rx) = mpsc::sync_channel(1); // Model --> View
-    let (ui_tx, ui_rx) = mpsc::sync_channel(1); // Controller -> View
+    let (mutate_tx, mutate_rx) = mpsc::sync_channel(1); // View & Controller --> Model
+    let runstate_model_tx = mutate_tx.clone();
+    let (control_tx, control_rx) = mpsc::channel(); // View & Model --> Controller
+    let gw_control_tx = control_tx.clone();
+    //Controller --> View and Model --> View share one unbounded channel, so the
+    //View can wait on both at once, and neither ever waits on the View.
+    let (view_tx, view_rx) = common::view_channel();
+    let ui_tx = common::ViewSender::new(view_tx.clone());
+    let delta_tx = common::ViewSender::new(view_tx.clone());
+    let reply_tx = common::ViewSender::new(view_tx.clone());
+    let runstate_tx = common::ViewSender::new(view_tx);
 
     /* ---------------------------
      * ---------- MODEL ----------
      * ---------------------------
      */
-    // Init & Spawn the GameWorld thread
-    let gw_thread = thread::spawn(move || {
-        let mut gw = gameworld::GameWorld::new(mutate_rx, delta_tx, gw_ecs_ap);
-
-        loop {
-            match gw.tick() {
-                Ok(ticker) => match ticker {
-                    common::Ticker::ExitProgram => {
-                        break;
-                    }
-                    common::Ticker::Continue => {}
-                },
-                Err(e) => {
-                    println!("{}", e);
-                }
-            };
-        }
-    });
+    // Init & Spawn the GameWorld thread, named for debugging reports
+    let gw_thread = thread::Builder::new().name("gameworld".to_string()).spawn(move || {
+        let mut gw = gameworld::GameWorld::new(mutate_rx, delta_tx, gw_control_tx, gw_ecs_ap, gw_snapshots);
+
+        common::run_loop(&mut gw);
+    }).unwrap(); //panics on failure, which is desired
 
     /* ---------------------------
      * ---------- VIEW -----------
      * ---------------------------
      */
-    // Init & Spawn the TUI thread
-    let tui_thread = thread::spawn(move || {
-        let mut tui = tui::TUIState::new(ui_rx, delta_rx, mutate_tx, tui_ecs_ap);
-
-        loop {
-            match tui.tick() {
-                Ok(ticker) => match ticker {
-                    common::Ticker::ExitProgram => {
-                        break;
-                    }
-                    common::Ticker::Continue => {}
-                },
-                Err(e) => {
-                    println!("{}", e);
-                }
-            };
-        }
-    });
+    // Init & Spawn the TUI thread, named for debugging reports
+    let tui_thread = thread::Builder::new().name("tui".to_string()).spawn(move || {
+        let mut tui = tui::TUIState::new(view_rx, reply_tx, mutate_tx, control_tx, snapshots, key_help);
+
+        common::run_loop(&mut tui);
+    }).unwrap(); //panics on failure, which is desired
 
     /* ---------------------------
      * ------- CONTROLLER --------
      * ---------------------------
      */
 
-    //Enable raw input mode, so all user input is captured immediately, byte-by-byte, as-is.
-    crossterm::terminal::enable_raw_mode().unwrap(); //panics on failure, which is desired
-
     // Store JoinHandles on tui & gameworld threads in GameState struct
-    let mut gs = controller::MainState::new(gw_thread, tui_thread, ui_tx, ecs_ap);
-
-    loop {
-        match gs.tick() {
-            Ok(ticker) => match ticker {
-                common::Ticker::ExitProgram => {
-                    break;
-                }
-                common::Ticker::Continue => {}
-            },
-            Err(e) => {
-                println!("{}", e);
-            }
-        };
-    }
+    let mut gs = controller::MainState::new(
+        gw_thread,
+        tui_thread,
+        ui_tx,
+        runstate_tx,
+        runstate_model_tx,
+        control_rx,
+        ecs_ap,
+    )
+    .with_keybindings(keybindings);
+
+    common::run_loop(&mut gs);
 
     //----------- End & Clean Up -----------
-    println!("Joining...\r");
-    let (_, _) = gs.join_threads();
-    println!("Exiting...\r");
-    crossterm::terminal::disable_raw_mode().unwrap();
-    std::process::exit(0);
+    let problems = gs.join_threads(options.access_report.as_deref());
+    drop(terminal); //process::exit() would skip it, and the problems should be readable.
+
+    if problems.is_empty() {
+        println!("Exiting...");
+        std::process::exit(0);
+    }
+    for problem in problems {
+        eprintln!("{}", problem);
+    }
+    std::process::exit(1);
 }
@@ -0,0 +1,136 @@
+//Jerome M. St.Martin
+//June 27, 2022
+
+//-----------------------------------------------------------------------------
+//------------------------- Command-Line Options ------------------------------
+//-----------------------------------------------------------------------------
+
+/* goblin_rl [--trace-channels[=FILE]] [--access-report[=FILE]] [--keybindings FILE]
+ *     Plays the game, recording all channel traffic to FILE if asked to, and
+ *     writing the ECSAccessPoint's lock contention report on exit if asked to.
+ *     Keys are bound as FILE says, else as keybindings.json says if there
+ *     is one, else as built in. See user_input/keybindings.rs.
+ *
+ * goblin_rl --view-trace FILE [--channel NAME]... [--thread NAME]... [--paced]
+ *     Prints a recorded trace instead of playing, keeping only the records
+ *     from the given channels and threads, if any are given. --paced waits
+ *     between records as long as was waited between them when recorded.
+ */
+
+use std::path::PathBuf;
+
+use crate::error::Gremlin;
+
+const DEFAULT_TRACE_PATH: &str = "channel_trace.log";
+const DEFAULT_ACCESS_REPORT_PATH: &str = "access_report.txt";
+
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct Options {
+    pub trace_channels: Option<PathBuf>,
+    pub access_report: Option<PathBuf>,
+    pub keybindings: Option<PathBuf>,
+    pub view_trace: Option<TraceFilter>,
+}
+
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct TraceFilter {
+    pub path: PathBuf,
+    pub channels: Vec<String>, //Empty means every channel.
+    pub threads: Vec<String>,  //Empty means every thread.
+    pub paced: bool,
+}
+
+impl Options {
+    ///Takes the arguments without the program name, i.e. std::env::args().skip(1).
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, Gremlin> {
+        let mut options = Options::default();
+        let mut filter = TraceFilter::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value_of = |flag: &str| -> Result<String, Gremlin> {
+                args.next()
+                    .ok_or_else(|| Gremlin::InvalidArgs(format!("{} needs a value", flag)))
+            };
+
+            match arg.as_str() {
+                "--trace-channels" => options.trace_channels = Some(PathBuf::from(DEFAULT_TRACE_PATH)),
+                "--access-report" => options.access_report = Some(PathBuf::from(DEFAULT_ACCESS_REPORT_PATH)),
+                "--keybindings" => options.keybindings = Some(PathBuf::from(value_of("--keybindings")?)),
+                "--view-trace" => filter.path = PathBuf::from(value_of("--view-trace")?),
+                "--channel" => filter.channels.push(value_of("--channel")?),
+                "--thread" => filter.threads.push(value_of("--thread")?),
+                "--paced" => filter.paced = true,
+                _ => {
+                    if let Some(path) = arg.strip_prefix("--trace-channels=") {
+                        options.trace_channels = Some(PathBuf::from(path));
+                    } else if let Some(path) = arg.strip_prefix("--access-report=") {
+                        options.access_report = Some(PathBuf::from(path));
+                    } else {
+                        return Err(Gremlin::InvalidArgs(format!("unknown option {}", arg)));
+                    }
+                }
+            }
+        }
+
+        let filtering = !filter.channels.is_empty() || !filter.threads.is_empty() || filter.paced;
+        if filter.path.as_os_str().is_empty() {
+            if filtering {
+                return Err(Gremlin::InvalidArgs("--channel, --thread and --paced need --view-trace".to_string()));
+            }
+        } else {
+            options.view_trace = Some(filter);
+        }
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, Gremlin> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+        assert_eq!(
+            parse(&["--trace-channels"]).unwrap().trace_channels,
+            Some(PathBuf::from(DEFAULT_TRACE_PATH))
+        );
+        assert_eq!(
+            parse(&["--trace-channels=t.log"]).unwrap().trace_channels,
+            Some(PathBuf::from("t.log"))
+        );
+
+        let filter = parse(&["--channel", "MutateCommand", "--view-trace", "t.log", "--paced"])
+            .unwrap()
+            .view_trace
+            .unwrap();
+        assert_eq!(filter.path, PathBuf::from("t.log"));
+        assert_eq!(filter.channels, vec!["MutateCommand".to_string()]);
+        assert!(filter.threads.is_empty());
+        assert!(filter.paced);
+
+        assert_eq!(parse(&[]).unwrap().access_report, None);
+        assert_eq!(
+            parse(&["--access-report"]).unwrap().access_report,
+            Some(PathBuf::from(DEFAULT_ACCESS_REPORT_PATH))
+        );
+        assert_eq!(parse(&["--access-report=a.txt"]).unwrap().access_report, Some(PathBuf::from("a.txt")));
+
+        assert_eq!(
+            parse(&["--keybindings", "keys.json"]).unwrap().keybindings,
+            Some(PathBuf::from("keys.json"))
+        );
+        assert!(parse(&["--keybindings"]).is_err());
+
+        assert!(parse(&["--view-trace"]).is_err());
+        assert!(parse(&["--channel", "InputEvent"]).is_err());
+        assert!(parse(&["--fast"]).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+//Jerome M. St.Martin
+//June 30, 2022
+
+//-----------------------------------------------------------------------------
+//---------------- Raw Mode & the Alternate Screen, Always Undone -------------
+//-----------------------------------------------------------------------------
+
+/* The game draws on crossterm's alternate screen, in raw mode, with the cursor
+ * hidden and the mouse captured, leaving the shell's scrollback as it was. All
+ * of that is undone by whichever comes first: the TerminalGuard being dropped,
+ * or a panic on any of the three threads, via the hook installed below.
+ * Undoing it a second time does nothing.
+ */
+
+use std::io::{self, Write};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crossterm::{cursor, event, execute, terminal};
+
+use crate::error::Gremlin;
+
+//Whether the terminal is currently set up for the game, and so needs restoring.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+///Restores the terminal when dropped. Note that std::process::exit() skips
+///destructors, so drop this before calling it.
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    pub fn enter() -> Result<Self, Gremlin> {
+        //Raw mode, so all user input is captured immediately, byte-by-byte, as-is.
+        terminal::enable_raw_mode()?;
+        ACTIVE.store(true, Ordering::SeqCst);
+        set_up(&mut io::stdout())?;
+
+        Ok(TerminalGuard { _private: () })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+///Safe to call from any thread, any number of times.
+pub fn restore() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = tear_down(&mut io::stdout());
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+///The terminal is restored before the default hook prints the panic message,
+///so the message is readable, and stays in the shell's scrollback.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        default_hook(info);
+    }));
+}
+
+fn set_up<W: Write>(out: &mut W) -> Result<(), Gremlin> {
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide, event::EnableMouseCapture)?;
+    Ok(())
+}
+
+fn tear_down<W: Write>(out: &mut W) -> Result<(), Gremlin> {
+    execute!(out, event::DisableMouseCapture, cursor::Show, terminal::LeaveAlternateScreen)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_set_up_and_tear_down() {
+        let mut out: Vec<u8> = Vec::new();
+        set_up(&mut out).unwrap();
+        let set_up = String::from_utf8(out.split_off(0)).unwrap();
+        assert!(set_up.contains("\x1b[?1049h")); //Enter the alternate screen
+        assert!(set_up.contains("\x1b[?25l")); //Hide the cursor
+        assert!(set_up.contains("\x1b[?1003h")); //Report every mouse movement
+
+        tear_down(&mut out).unwrap();
+        let tear_down = String::from_utf8(out).unwrap();
+        assert!(tear_down.contains("\x1b[?25h"));
+        assert!(tear_down.contains("\x1b[?1003l"));
+        assert!(tear_down.contains("\x1b[?1049l"));
+        //The cursor is shown before leaving, so it is shown on the main screen too.
+        assert!(tear_down.find("\x1b[?25h") < tear_down.find("\x1b[?1049l"));
+
+        //Never entered in tests, so there is nothing to restore.
+        restore();
+        assert!(!ACTIVE.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,189 @@
+//Jerome M. St.Martin
+//July 3, 2022
+
+//-----------------------------------------------------------------------------
+//------------- Count Prefixes & Prefix Keys, e.g. 5j, g h, 12F l -------------
+//-----------------------------------------------------------------------------
+
+/* On the player's turn, input goes through here before the TUI turns it into
+ * a command. Digits build up a count, and Go or Fight wait for a direction;
+ * nothing is sent until the sequence is complete, then exactly one InputEvent
+ * comes out for it:
+ *
+ *     5 j      Repeat { count: 5, input: Hjkl(S) }
+ *     g h      Run(W)
+ *     12 F l   Repeat { count: 12, input: Attack(E) }
+ *
+ * Cancel throws away whatever is pending. Anything else that cannot finish the
+ * sequence throws it away too, along with itself.
+ */
+
+use crate::common::InputEvent;
+
+//Anything longer is almost certainly a typo.
+const MAX_COUNT: u16 = 99;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Prefix {
+    Go,
+    Fight,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub(super) enum Parsed {
+    Pending,              //Part of a sequence; wait for more.
+    Cancelled,            //The pending sequence was thrown away.
+    Complete(InputEvent), //Handle this as if it was the only input.
+}
+
+#[derive(Default, Debug)]
+pub(super) struct InputSequence {
+    count: Option<u16>,
+    prefix: Option<Prefix>,
+}
+
+impl InputSequence {
+    pub(super) fn feed(&mut self, input: InputEvent) -> Parsed {
+        let dir = match input {
+            //Nothing to do with any sequence, so they leave it as it is.
+            InputEvent::Hover { .. } | InputEvent::Resize { .. } | InputEvent::Exit | InputEvent::Null => {
+                return Parsed::Complete(input)
+            }
+            InputEvent::Count(digit) if self.prefix.is_none() => {
+                let count = self.count.unwrap_or(0) * 10 + digit as u16;
+                self.count = Some(count.min(MAX_COUNT)); //Never overflows, as MAX_COUNT * 10 + 9 fits.
+                return Parsed::Pending;
+            }
+            InputEvent::Go | InputEvent::Fight if self.prefix.is_none() => {
+                self.prefix = Some(if input == InputEvent::Go { Prefix::Go } else { Prefix::Fight });
+                return Parsed::Pending;
+            }
+            InputEvent::Cancel if !self.is_empty() => {
+                self.clear();
+                return Parsed::Cancelled;
+            }
+            InputEvent::Hjkl(dir) | InputEvent::Wasd(dir) => Some(dir),
+            _ => None,
+        };
+
+        let completed = match (self.prefix, dir) {
+            (None, Some(_)) => input,
+            (None, None) if self.count.is_none() => input,
+            (Some(Prefix::Go), Some(dir)) => InputEvent::Run(dir),
+            (Some(Prefix::Fight), Some(dir)) => InputEvent::Attack(dir),
+            //Only a direction can be counted.
+            (_, None) => {
+                self.clear();
+                return Parsed::Cancelled;
+            }
+        };
+        let count = self.count;
+        self.clear();
+
+        match count {
+            Some(count) if count > 1 => Parsed::Complete(InputEvent::Repeat {
+                count,
+                input: Box::new(completed),
+            }),
+            _ => Parsed::Complete(completed),
+        }
+    }
+
+    ///For the status bar, e.g. "5 go", or None if nothing is pending.
+    pub(super) fn pending(&self) -> Option<String> {
+        let prefix = self.prefix.map(|prefix| match prefix {
+            Prefix::Go => "go",
+            Prefix::Fight => "fight",
+        });
+        match (self.count, prefix) {
+            (None, None) => None,
+            (Some(count), None) => Some(count.to_string()),
+            (None, Some(prefix)) => Some(prefix.to_string()),
+            (Some(count), Some(prefix)) => Some(format!("{} {}", count, prefix)),
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.count = None;
+        self.prefix = None;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.count.is_none() && self.prefix.is_none()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::common::Dir;
+
+    fn step(dir: Dir) -> InputEvent {
+        InputEvent::Hjkl(dir)
+    }
+
+    fn feed_all(sequence: &mut InputSequence, inputs: Vec<InputEvent>) -> Vec<Parsed> {
+        inputs.into_iter().map(|input| sequence.feed(input)).collect()
+    }
+
+    #[test]
+    fn test_count_and_prefix() {
+        let mut sequence = InputSequence::default();
+
+        let parsed = feed_all(&mut sequence, vec![InputEvent::Count(5), step(Dir::S)]);
+        assert_eq!(
+            parsed,
+            vec![
+                Parsed::Pending,
+                Parsed::Complete(InputEvent::Repeat { count: 5, input: Box::new(step(Dir::S)) })
+            ]
+        );
+
+        assert_eq!(sequence.feed(InputEvent::Go), Parsed::Pending);
+        assert_eq!(sequence.feed(step(Dir::W)), Parsed::Complete(InputEvent::Run(Dir::W)));
+
+        feed_all(&mut sequence, vec![InputEvent::Count(1), InputEvent::Count(2), InputEvent::Fight]);
+        assert_eq!(sequence.pending().as_deref(), Some("12 fight"));
+        assert_eq!(
+            sequence.feed(InputEvent::Wasd(Dir::E)),
+            Parsed::Complete(InputEvent::Repeat { count: 12, input: Box::new(InputEvent::Attack(Dir::E)) })
+        );
+        assert_eq!(sequence.pending(), None);
+
+        //A count of one, or none at all, is no count.
+        feed_all(&mut sequence, vec![InputEvent::Count(0), InputEvent::Count(1)]);
+        assert_eq!(sequence.feed(step(Dir::N)), Parsed::Complete(step(Dir::N)));
+        assert_eq!(sequence.feed(InputEvent::Confirm), Parsed::Complete(InputEvent::Confirm));
+
+        feed_all(&mut sequence, vec![InputEvent::Count(9); 4]);
+        assert_eq!(sequence.pending().as_deref(), Some("99"));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut sequence = InputSequence::default();
+
+        feed_all(&mut sequence, vec![InputEvent::Count(3), InputEvent::Go]);
+        let hover = InputEvent::Hover { column: 1, row: 1 };
+        assert_eq!(sequence.feed(hover.clone()), Parsed::Complete(hover));
+        assert_eq!(sequence.pending().as_deref(), Some("3 go"));
+        assert_eq!(sequence.feed(InputEvent::Cancel), Parsed::Cancelled);
+        assert_eq!(sequence.pending(), None);
+
+        //With nothing pending, Cancel is just Cancel.
+        assert_eq!(sequence.feed(InputEvent::Cancel), Parsed::Complete(InputEvent::Cancel));
+
+        //A prefix only takes a direction.
+        feed_all(&mut sequence, vec![InputEvent::Fight]);
+        assert_eq!(sequence.feed(InputEvent::Confirm), Parsed::Cancelled);
+        feed_all(&mut sequence, vec![InputEvent::Go]);
+        assert_eq!(sequence.feed(InputEvent::Count(4)), Parsed::Cancelled);
+        assert_eq!(sequence.pending(), None);
+
+        //So does a count.
+        feed_all(&mut sequence, vec![InputEvent::Count(5)]);
+        assert_eq!(sequence.feed(InputEvent::Confirm), Parsed::Cancelled);
+        assert_eq!(sequence.pending(), None);
+    }
+}
@@ -0,0 +1,198 @@
+//Jerome M. St.Martin
+//July 4, 2022
+
+//-----------------------------------------------------------------------------
+//------------------ One Line of Typed Text, with History ---------------------
+//-----------------------------------------------------------------------------
+
+/* Fed the InputEvents the Controller forwards in text entry: Char, Backspace,
+ * Delete, Home and End; Hjkl(W) and Hjkl(E) move the cursor, Hjkl(N) and
+ * Hjkl(S) step back and forth through earlier lines. Confirm hands the line
+ * over and remembers it; Cancel throws it away.
+ */
+
+use crate::common::{Dir, InputEvent};
+
+#[derive(PartialEq, Eq, Debug)]
+pub(super) enum Edit {
+    Editing,
+    Submitted(String),
+    Cancelled,
+}
+
+#[derive(Debug)]
+pub(super) struct LineEdit {
+    text: Vec<char>,
+    cursor: usize,           //Where the next Char goes, 0..=text.len().
+    max_len: usize,
+    accepts: fn(char) -> bool,
+    history: Vec<String>,    //Oldest first.
+    recalled: Option<usize>, //Index into history of the line shown, if one is.
+    draft: Vec<char>,        //What was being typed, before stepping back into history.
+}
+
+impl LineEdit {
+    pub(super) fn new(max_len: usize, accepts: fn(char) -> bool) -> Self {
+        LineEdit {
+            text: Vec::new(),
+            cursor: 0,
+            max_len,
+            accepts,
+            history: Vec::new(),
+            recalled: None,
+            draft: Vec::new(),
+        }
+    }
+
+    ///Starts a new line with some text already typed, the cursor after it.
+    pub(super) fn start(&mut self, text: &str) {
+        self.set_text(text.chars().filter(|c| (self.accepts)(*c)).take(self.max_len).collect());
+        self.recalled = None;
+    }
+
+    pub(super) fn handle(&mut self, input: &InputEvent) -> Edit {
+        match input {
+            InputEvent::Char(c) if (self.accepts)(*c) && self.text.len() < self.max_len => {
+                self.text.insert(self.cursor, *c);
+                self.cursor += 1;
+            }
+            InputEvent::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.cursor);
+            }
+            InputEvent::Delete if self.cursor < self.text.len() => {
+                self.text.remove(self.cursor);
+            }
+            InputEvent::Hjkl(Dir::W) => self.cursor = self.cursor.saturating_sub(1),
+            InputEvent::Hjkl(Dir::E) => self.cursor = (self.cursor + 1).min(self.text.len()),
+            InputEvent::Home => self.cursor = 0,
+            InputEvent::End => self.cursor = self.text.len(),
+            InputEvent::Hjkl(Dir::N) => self.recall_older(),
+            InputEvent::Hjkl(Dir::S) => self.recall_newer(),
+            InputEvent::Confirm => {
+                let line: String = self.text.iter().collect();
+                if !line.is_empty() && self.history.last() != Some(&line) {
+                    self.history.push(line.clone());
+                }
+                self.start("");
+                return Edit::Submitted(line);
+            }
+            InputEvent::Cancel => {
+                self.start("");
+                return Edit::Cancelled;
+            }
+            _ => {}
+        }
+        Edit::Editing
+    }
+
+    pub(super) fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    pub(super) fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    ///The text with a '|' where the cursor is, for drawing on one line.
+    pub(super) fn display(&self) -> String {
+        let mut shown = self.text.clone();
+        shown.insert(self.cursor, '|');
+        shown.into_iter().collect()
+    }
+
+    fn recall_older(&mut self) {
+        let older = match self.recalled {
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            }
+            Some(0) => return,
+            Some(recalled) => recalled - 1,
+        };
+        self.recalled = Some(older);
+        self.set_text(self.history[older].chars().collect());
+    }
+
+    fn recall_newer(&mut self) {
+        match self.recalled {
+            None => {}
+            Some(recalled) if recalled + 1 < self.history.len() => {
+                self.recalled = Some(recalled + 1);
+                self.set_text(self.history[recalled + 1].chars().collect());
+            }
+            Some(_) => {
+                self.recalled = None;
+                let draft = std::mem::take(&mut self.draft);
+                self.set_text(draft);
+            }
+        }
+    }
+
+    fn set_text(&mut self, text: Vec<char>) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn type_in(edit: &mut LineEdit, text: &str) {
+        for c in text.chars() {
+            edit.handle(&InputEvent::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut edit = LineEdit::new(6, |c| c.is_ascii_alphabetic());
+        type_in(&mut edit, "gob1lin!!");
+        assert_eq!(edit.text(), "goblin");
+
+        edit.handle(&InputEvent::Hjkl(Dir::W));
+        edit.handle(&InputEvent::Hjkl(Dir::W));
+        edit.handle(&InputEvent::Backspace);
+        assert_eq!(edit.display(), "gob|in");
+        edit.handle(&InputEvent::Home);
+        edit.handle(&InputEvent::Delete);
+        type_in(&mut edit, "j");
+        assert_eq!(edit.display(), "j|obin");
+        edit.handle(&InputEvent::End);
+        assert_eq!(edit.cursor(), 5);
+
+        assert_eq!(edit.handle(&InputEvent::Confirm), Edit::Submitted("jobin".to_string()));
+        assert_eq!(edit.text(), "");
+        type_in(&mut edit, "orc");
+        assert_eq!(edit.handle(&InputEvent::Cancel), Edit::Cancelled);
+        assert_eq!(edit.text(), "");
+    }
+
+    #[test]
+    fn test_history() {
+        let mut edit = LineEdit::new(20, |c| !c.is_control());
+        for line in ["wait", "descend", "descend"] {
+            type_in(&mut edit, line);
+            edit.handle(&InputEvent::Confirm);
+        }
+        edit.handle(&InputEvent::Confirm); //Empty lines are not remembered.
+
+        type_in(&mut edit, "tra");
+        edit.handle(&InputEvent::Hjkl(Dir::N));
+        assert_eq!(edit.text(), "descend");
+        edit.handle(&InputEvent::Hjkl(Dir::N));
+        edit.handle(&InputEvent::Hjkl(Dir::N)); //Already at the oldest.
+        assert_eq!(edit.text(), "wait");
+        assert_eq!(edit.cursor(), 4);
+
+        edit.handle(&InputEvent::Hjkl(Dir::S));
+        assert_eq!(edit.text(), "descend");
+        edit.handle(&InputEvent::Hjkl(Dir::S));
+        assert_eq!(edit.text(), "tra"); //Back to what was being typed.
+        edit.handle(&InputEvent::Hjkl(Dir::S));
+        assert_eq!(edit.text(), "tra");
+    }
+}
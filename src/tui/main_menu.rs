@@ -0,0 +1,229 @@
+//Jerome M. St.Martin
+//June 29, 2022
+
+//-----------------------------------------------------------------------------
+//------------------- The Title Screen, in RunState::MainMenu -----------------
+//-----------------------------------------------------------------------------
+
+/* Up and down pick an item; Confirm chooses it. New Game first asks for a seed,
+ * typed in text entry, starting from a random one; up and down bring back the
+ * seeds of earlier games. Cancel goes back a screen. There is no Continue, as
+ * nothing saves a game yet.
+ */
+
+use super::line_edit::{Edit, LineEdit};
+use crate::common::{Dir, InputEvent};
+
+//How many digits a new seed starts with, and how many can be typed; any 19 fit in a u64.
+const SEED_DIGITS: usize = 8;
+const MAX_SEED_DIGITS: usize = 19;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) enum MenuItem {
+    NewGame,
+    Options,
+    Quit,
+}
+
+const ITEMS: [MenuItem; 3] = [MenuItem::NewGame, MenuItem::Options, MenuItem::Quit];
+
+#[derive(PartialEq, Eq, Debug)]
+enum Screen {
+    Items,
+    SeedEntry,
+    Options,
+}
+
+///What the player chose, once they have chosen something the TUI must act on.
+#[derive(PartialEq, Eq, Debug)]
+pub(super) enum MenuChoice {
+    NewGame { seed: u64 },
+    Quit,
+}
+
+#[derive(Debug)]
+pub(super) struct MainMenu {
+    selected: usize, //Index into ITEMS.
+    screen: Screen,
+    seed: LineEdit,        //Kept across games, for its history.
+    key_help: Vec<String>, //From the Keybindings the Controller translates keys with.
+}
+
+impl MainMenu {
+    pub(super) fn new(key_help: Vec<String>) -> Self {
+        MainMenu {
+            selected: 0,
+            screen: Screen::Items,
+            seed: LineEdit::new(MAX_SEED_DIGITS, |c| c.is_ascii_digit()),
+            key_help,
+        }
+    }
+
+    ///Back to the first screen, as if new, but remembering earlier seeds.
+    pub(super) fn reset(&mut self) {
+        self.selected = 0;
+        self.screen = Screen::Items;
+    }
+
+    ///Whether the Controller should be in text entry.
+    pub(super) fn wants_text(&self) -> bool {
+        self.screen == Screen::SeedEntry
+    }
+
+    pub(super) fn selected(&self) -> MenuItem {
+        ITEMS[self.selected]
+    }
+
+    pub(super) fn handle(&mut self, input: &InputEvent) -> Option<MenuChoice> {
+        let dir = match input {
+            InputEvent::Hjkl(dir) | InputEvent::Wasd(dir) => Some(*dir),
+            _ => None,
+        };
+
+        match &mut self.screen {
+            Screen::Items => match (input, dir) {
+                (_, Some(Dir::N)) => self.step(ITEMS.len() - 1),
+                (_, Some(Dir::S)) => self.step(1),
+                (InputEvent::Confirm, _) => return self.choose(),
+                _ => {}
+            },
+            Screen::SeedEntry => match self.seed.handle(input) {
+                Edit::Submitted(typed) => {
+                    //Only ever digits, and never too many of them; an empty seed is a random one.
+                    let seed = typed.parse().unwrap_or_else(|_| rand::random());
+                    self.screen = Screen::Items;
+                    return Some(MenuChoice::NewGame { seed });
+                }
+                Edit::Cancelled => self.screen = Screen::Items,
+                Edit::Editing => {}
+            },
+            Screen::Options => {
+                if let InputEvent::Cancel | InputEvent::Confirm = input {
+                    self.screen = Screen::Items;
+                }
+            }
+        }
+
+        None
+    }
+
+    //Moves the selection by offset, wrapping around.
+    fn step(&mut self, offset: usize) {
+        self.selected = (self.selected + offset) % ITEMS.len();
+    }
+
+    fn choose(&mut self) -> Option<MenuChoice> {
+        match self.selected() {
+            MenuItem::NewGame => {
+                self.seed.start(&random_digits());
+                self.screen = Screen::SeedEntry;
+                None
+            }
+            MenuItem::Options => {
+                self.screen = Screen::Options;
+                None
+            }
+            MenuItem::Quit => Some(MenuChoice::Quit),
+        }
+    }
+
+    ///What to draw, one line each.
+    pub(super) fn lines(&self) -> Vec<String> {
+        let mut lines = vec!["GoblinRL".to_string(), String::new()];
+
+        match &self.screen {
+            Screen::Items => {
+                for (i, item) in ITEMS.iter().enumerate() {
+                    let marker = if i == self.selected { '>' } else { ' ' };
+                    let label = match item {
+                        MenuItem::NewGame => "New Game",
+                        MenuItem::Options => "Options",
+                        MenuItem::Quit => "Quit",
+                    };
+                    lines.push(format!("{} {}", marker, label));
+                }
+            }
+            Screen::SeedEntry => {
+                lines.push(format!("Seed: {}", self.seed.text()));
+                lines.push(format!("      {}^", " ".repeat(self.seed.cursor())));
+                lines.push(String::new());
+                lines.push("Type a seed. Up/down: earlier seeds.".to_string());
+                lines.push("Enter: start. Esc: back.".to_string());
+            }
+            Screen::Options => {
+                lines.extend(self.key_help.iter().cloned());
+                lines.push(String::new());
+                lines.push("Esc: back.".to_string());
+            }
+        }
+
+        lines
+    }
+}
+
+fn random_digits() -> String {
+    (0..SEED_DIGITS).map(|_| char::from(b'0' + rand::random::<u8>() % 10)).collect()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_navigation() {
+        let mut menu = MainMenu::new(Vec::new());
+        assert_eq!(menu.selected(), MenuItem::NewGame);
+
+        menu.handle(&InputEvent::Hjkl(Dir::S));
+        assert_eq!(menu.selected(), MenuItem::Options);
+        menu.handle(&InputEvent::Wasd(Dir::S));
+        menu.handle(&InputEvent::Wasd(Dir::S));
+        assert_eq!(menu.selected(), MenuItem::NewGame); //Wrapped around.
+        menu.handle(&InputEvent::Hjkl(Dir::N));
+        assert_eq!(menu.selected(), MenuItem::Quit);
+        assert_eq!(menu.handle(&InputEvent::Confirm), Some(MenuChoice::Quit));
+    }
+
+    #[test]
+    fn test_seed_entry() {
+        let mut menu = MainMenu::new(Vec::new());
+        assert_eq!(menu.handle(&InputEvent::Confirm), None);
+        assert!(menu.wants_text());
+        assert_eq!(menu.seed.text().len(), SEED_DIGITS);
+        menu.seed.start("");
+
+        for c in "91x00001".chars() {
+            menu.handle(&InputEvent::Char(c));
+        }
+        menu.handle(&InputEvent::Home);
+        menu.handle(&InputEvent::Hjkl(Dir::E));
+        menu.handle(&InputEvent::Char('3'));
+        assert!(menu.lines().contains(&"Seed: 93100001".to_string()));
+        assert!(menu.lines().contains(&"        ^".to_string()));
+        assert_eq!(menu.handle(&InputEvent::Confirm), Some(MenuChoice::NewGame { seed: 93100001 }));
+        assert_eq!(menu.screen, Screen::Items);
+        assert!(!menu.wants_text());
+
+        //The last game's seed is one step back, even after going back to the first screen.
+        menu.reset();
+        menu.handle(&InputEvent::Confirm);
+        menu.handle(&InputEvent::Hjkl(Dir::N));
+        assert_eq!(menu.seed.text(), "93100001");
+        assert_eq!(menu.handle(&InputEvent::Cancel), None);
+        assert_eq!(menu.screen, Screen::Items);
+    }
+
+    #[test]
+    fn test_options_and_lines() {
+        let mut menu = MainMenu::new(vec!["Move: k j".to_string(), "Menu: F1".to_string()]);
+        assert_eq!(menu.lines()[2..], ["> New Game", "  Options", "  Quit"]);
+
+        menu.handle(&InputEvent::Hjkl(Dir::S));
+        menu.handle(&InputEvent::Confirm);
+        assert_eq!(menu.screen, Screen::Options);
+        assert_eq!(menu.lines()[2..5], ["Move: k j", "Menu: F1", ""]);
+        menu.handle(&InputEvent::Cancel);
+        assert_eq!(menu.screen, Screen::Items);
+    }
+}
@@ -1,71 +1,806 @@
 //Jerome M. St.Martin
 //May, 2022
 
+use std::io::{self, Write};
 use std::sync::{
-    mpsc::{Receiver, SyncSender},
+    mpsc::{Receiver, RecvError, RecvTimeoutError, Sender, SyncSender},
     Arc,
 };
+use std::time::{Duration, Instant};
 
-use crate::common::{DeltaNotification, InputEvent, MutateCommand, Ticker};
-use crate::ecs_access_point::ECSAccessPoint;
+use crate::channel_trace;
+use crate::common::{
+    CommandOutcome, CommandReply, CommandRequest, ControlRequest, Coords, Dir, InputEvent, ModelEvent, MutateCommand,
+    RenderSnapshot, RunState, SnapshotSlot, Target, TickLoop, Ticker, ViewEvent, ViewSender,
+};
 use crate::error::Gremlin;
 
+mod input_sequence;
+mod line_edit;
+mod main_menu;
+#[allow(dead_code)] //Not wired in yet.
 mod observer;
+mod render;
+
+use input_sequence::{InputSequence, Parsed};
+use line_edit::{Edit, LineEdit};
+use main_menu::{MainMenu, MenuChoice};
+use render::Layout;
+
+//How often the screen is redrawn, if anything changed since the last time.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+
+//Assumed if the terminal cannot say how big it is; the first resize corrects it.
+const FALLBACK_SIZE: (u16, u16) = (80, 24);
+
+//Long enough for any command, short enough for the message line.
+const MAX_PROMPT_LEN: usize = 38;
 
 pub struct TUIState {
-    ctrlr_channel: Receiver<InputEvent>,
-    model_channel: (Receiver<DeltaNotification>, SyncSender<MutateCommand>),
-    ecs_ap: Arc<ECSAccessPoint>,
+    inbox: Receiver<ViewEvent>, //Controller input, model deltas, replies and RunStates, all in one.
+    model_tx: SyncSender<ModelEvent>,
+    reply_tx: ViewSender<CommandReply>, //Attached to every command sent; replies come back to the inbox.
+    control_tx: Sender<ControlRequest>, //Asks the Controller for a change of RunState, or to quit.
+    runstate: RunState,                 //As last sent by the Controller.
+    menu: MainMenu,                     //Drawn and given all input in RunState::MainMenu.
+    sequence: InputSequence,            //Count prefixes and prefix keys, on the player's turn.
+    prompt: LineEdit,                   //The command prompt, kept for its history.
+    prompting: bool,                    //Whether the prompt is open, which only it is on the player's turn.
+    text_entry: bool,                   //As last asked of the Controller.
+    inputs: u64,                        //InputEvents handled so far, told to the Controller with text_entry.
+    snapshots: Arc<SnapshotSlot<RenderSnapshot>>, //Published by the GameWorld thread.
+    snapshot: Box<RenderSnapshot>,                //The newest one taken so far; draw from this.
+    message: Option<String>,                      //Why the last command was rejected, if it was.
+    looking_at: Option<Coords>,                   //The tile under the mouse, if any.
+    size: (u16, u16),                             //Of the terminal, as (columns, rows).
+    layout: Option<Layout>,                       //None if the terminal is too small for the map.
+    out: Box<dyn Write + Send>,
+    next_redraw: Instant,
+    dirty: bool, //Whether anything has changed since the last redraw.
 }
 
 impl TUIState {
     pub fn new(
-        ctrlr_rx: Receiver<InputEvent>,
-        model_rx: Receiver<DeltaNotification>,
-        model_tx: SyncSender<MutateCommand>,
-        ecs_ap: Arc<ECSAccessPoint>,
+        inbox: Receiver<ViewEvent>,
+        reply_tx: ViewSender<CommandReply>,
+        model_tx: SyncSender<ModelEvent>,
+        control_tx: Sender<ControlRequest>,
+        snapshots: Arc<SnapshotSlot<RenderSnapshot>>,
+        key_help: Vec<String>,
     ) -> Self {
+        //The GameWorld publishes one before its first tick, which may already be in.
+        let snapshot = snapshots.take().unwrap_or_default();
+        let size = crossterm::terminal::size().unwrap_or(FALLBACK_SIZE);
         TUIState {
-            ctrlr_channel: (ctrlr_rx),
-            model_channel: (model_rx, model_tx),
-            ecs_ap,
+            inbox,
+            model_tx,
+            reply_tx,
+            control_tx,
+            runstate: RunState::MainMenu,
+            menu: MainMenu::new(key_help),
+            sequence: InputSequence::default(),
+            prompt: LineEdit::new(MAX_PROMPT_LEN, |c| !c.is_control()),
+            prompting: false,
+            text_entry: false,
+            inputs: 0,
+            snapshots,
+            layout: Layout::new(size.0, size.1, snapshot.map_size),
+            snapshot,
+            message: None,
+            looking_at: None,
+            size,
+            out: Box::new(io::stdout()),
+            next_redraw: Instant::now(),
+            dirty: true,
+        }
+    }
+
+    fn handle(&mut self, event: ViewEvent) -> Result<Ticker, Gremlin> {
+        match event {
+            ViewEvent::Input(input) => {
+                self.inputs += 1;
+                let handled = self.handle_input(input);
+                //Answered even if handling it went wrong; the Controller reads no more keys until then.
+                if !matches!(handled, Ok(Ticker::ExitProgram)) {
+                    self.ask_for_text_entry(true)?;
+                }
+                return handled;
+            }
+            //Nothing is drawn from deltas directly yet; the next snapshot shows the change.
+            ViewEvent::Delta(_) => {}
+            ViewEvent::Reply(reply) => self.handle_reply(reply),
+            ViewEvent::RunState(state) => {
+                //Back at the title screen after a game.
+                if state == RunState::MainMenu {
+                    self.menu.reset();
+                    self.message = None;
+                }
+                //A half-typed sequence, or command, does not outlive the turn it was typed in.
+                if !matches!(state, RunState::AwaitingInput { .. }) {
+                    self.sequence.clear();
+                    self.prompt.start("");
+                    self.prompting = false;
+                }
+                self.runstate = state;
+                self.ask_for_text_entry(false)?;
+            }
         }
+
+        self.dirty = true;
+        Ok(Ticker::Continue)
     }
 
-    pub fn tick(&mut self) -> Result<Ticker, Gremlin> {
-        //println!("TUI thread calling recv()...\r"); // FOR TESTING ONLY
-        let message = self.ctrlr_channel.recv()?;
-        //println!("{:?}\r", message); // FOR TESTING ONLY
+    fn handle_input(&mut self, input: InputEvent) -> Result<Ticker, Gremlin> {
+        if let InputEvent::Resize { columns, rows } = input {
+            self.size = (columns, rows);
+            self.relayout();
+            self.next_redraw = Instant::now(); //Whatever was on screen is garbage now.
+            return Ok(Ticker::Continue);
+        }
+
+        if self.runstate == RunState::MainMenu && input != InputEvent::Exit {
+            self.handle_menu_input(input)?;
+            return Ok(Ticker::Continue);
+        }
+
+        //The mouse still looks around while a command is typed.
+        if self.prompting && !matches!(input, InputEvent::Hover { .. } | InputEvent::Exit) {
+            self.handle_prompt_input(input)?;
+            return Ok(Ticker::Continue);
+        }
 
-        //Process Message
-        match message {
-            InputEvent::Confirm => {
-                //Testing ECS Access Point
-                self.model_channel.1.send(MutateCommand::Test)?;
+        //Only the player's turn turns input into commands; the Controller routes the rest.
+        let players_turn = matches!(self.runstate, RunState::AwaitingInput { .. });
+        let input = if players_turn {
+            match self.sequence.feed(input) {
+                Parsed::Complete(input) => input,
+                Parsed::Pending | Parsed::Cancelled => return Ok(Ticker::Continue),
+            }
+        } else {
+            input
+        };
+
+        match input {
+            InputEvent::Repeat { count, input } if players_turn => {
+                if let Some(command) = command_for(&input) {
+                    self.send_command(MutateCommand::Repeat(count, Box::new(command)))?;
+                }
+            }
+            InputEvent::Prompt if players_turn => self.prompting = true,
+            InputEvent::Click { column, row } if players_turn => {
+                if let Some(to) = self.layout.and_then(|layout| layout.map_coords(column, row)) {
+                    self.send_command(MutateCommand::Travel(to))?;
+                }
+            }
+            InputEvent::Hover { column, row } => {
+                self.looking_at = self.layout.and_then(|layout| layout.map_coords(column, row));
             }
             InputEvent::Exit => {
-                self.pre_exit(&self.model_channel.1)?;
+                self.pre_exit(&self.model_tx)?;
                 return Ok(Ticker::ExitProgram);
             }
+            input if players_turn => {
+                if let Some(command) = command_for(&input) {
+                    self.send_command(command)?;
+                }
+            }
             _ => {}
         }
 
         Ok(Ticker::Continue)
     }
 
-    fn pre_exit(&self, gw_tx: &SyncSender<MutateCommand>) -> Result<Ticker, Gremlin> {
-        //Tell GameWorld thread to finish
-        gw_tx.send(MutateCommand::Exit)?;
+    fn handle_menu_input(&mut self, input: InputEvent) -> Result<(), Gremlin> {
+        match self.menu.handle(&input) {
+            Some(MenuChoice::NewGame { seed }) => {
+                //Sent before asking for PreRun, so the GameWorld has the seed before the Controller's PreRun.
+                let new_game = ModelEvent::NewGame { seed };
+                channel_trace::record(&new_game);
+                self.model_tx.send(new_game)?;
+                self.ask(ControlRequest::Transition(RunState::PreRun))?;
+            }
+            Some(MenuChoice::Quit) => self.ask(ControlRequest::Quit)?,
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn handle_prompt_input(&mut self, input: InputEvent) -> Result<(), Gremlin> {
+        match self.prompt.handle(&input) {
+            Edit::Submitted(line) => {
+                self.prompting = false;
+                match prompt_command(&line, &self.snapshot) {
+                    Ok(Some(command)) => self.send_command(command)?,
+                    Ok(None) => {}
+                    Err(message) => self.message = Some(message),
+                }
+            }
+            Edit::Cancelled => self.prompting = false,
+            Edit::Editing => {}
+        }
+        Ok(())
+    }
+
+    //Keys are only typed as text while something on screen wants them. The Controller
+    //is told after every input, answering or not, and otherwise only of a change.
+    fn ask_for_text_entry(&mut self, answering: bool) -> Result<(), Gremlin> {
+        let wanted = match self.runstate {
+            RunState::MainMenu => self.menu.wants_text(),
+            RunState::AwaitingInput { .. } => self.prompting,
+            _ => false,
+        };
+        if answering || wanted != self.text_entry {
+            self.text_entry = wanted;
+            self.ask(ControlRequest::TextEntry { on: wanted, inputs: self.inputs })?;
+        }
+        Ok(())
+    }
+
+    fn ask(&self, request: ControlRequest) -> Result<(), Gremlin> {
+        channel_trace::record(&request);
+        self.control_tx.send(request)?;
+        Ok(())
+    }
+
+    //Does not wait for the reply; it arrives in the inbox like everything else.
+    //The Controller is asked to wait for the GameWorld first, so that the
+    //GameWorld's answer, sent after the command, always comes after the asking.
+    fn send_command(&self, command: MutateCommand) -> Result<(), Gremlin> {
+        self.ask(ControlRequest::Transition(RunState::GameWorld))?;
+
+        let request = CommandRequest::with_reply(command, self.reply_tx.clone());
+        channel_trace::record(&request);
+        self.model_tx.send(request.into())?;
+        Ok(())
+    }
+
+    //Replies arrive in the order their commands were sent, so the last one wins.
+    fn handle_reply(&mut self, reply: CommandReply) {
+        self.message = match reply.outcome {
+            CommandOutcome::Accepted => None,
+            CommandOutcome::Rejected(rejection) => Some(rejection.to_string()),
+        };
+    }
+
+    fn relayout(&mut self) {
+        let (columns, rows) = self.size;
+        self.layout = Layout::new(columns, rows, self.snapshot.map_size);
+        self.looking_at = None;
+    }
+
+    //Never blocks: keeps the current snapshot if the GameWorld has not published a newer one.
+    fn redraw(&mut self) -> Result<(), Gremlin> {
+        if let Some(newest) = self.snapshots.take() {
+            let resized = newest.map_size != self.snapshot.map_size;
+            self.snapshot = newest;
+            if resized {
+                self.relayout();
+            }
+            self.dirty = true;
+        }
+
+        if self.dirty {
+            match self.runstate {
+                //Neither of these draws the map, so neither needs room for it.
+                RunState::MainMenu => {
+                    let mut lines = self.menu.lines();
+                    lines.extend(self.message.iter().cloned());
+                    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+                    render::draw_screen(&mut self.out, &lines)?;
+                }
+                RunState::GameOver => render::draw_screen(&mut self.out, &["Game Over", "", "Press Enter."])?,
+                _ => match self.layout {
+                    Some(layout) => {
+                        let look = self.looking_at.map(|at| render::describe(&self.snapshot, at));
+                        //The sequence typed so far leads the message line, until it is complete.
+                        let message = match (self.sequence.pending(), &self.message) {
+                            _ if self.prompting => Some(format!(":{}", self.prompt.display())),
+                            (Some(pending), Some(message)) => Some(format!("[{}] {}", pending, message)),
+                            (Some(pending), None) => Some(format!("[{}]", pending)),
+                            (None, message) => message.clone(),
+                        };
+                        render::draw(&mut self.out, &layout, &self.snapshot, look.as_deref(), message.as_deref())?;
+                    }
+                    None => {
+                        let (columns, rows) = self.size;
+                        render::draw_too_small(&mut self.out, columns, rows, self.snapshot.map_size)?;
+                    }
+                },
+            }
+            self.dirty = false;
+        }
+
+        Ok(())
+    }
+
+    fn pre_exit(&self, gw_tx: &SyncSender<ModelEvent>) -> Result<Ticker, Gremlin> {
+        //Tell GameWorld thread to finish; if it already has, there is no one left to tell.
+        let request: CommandRequest = MutateCommand::Exit.into();
+        channel_trace::record(&request);
+        let _ = gw_tx.send(request.into());
 
         Ok(Ticker::ExitProgram)
     }
 }
 
+//The commands a count prefix can repeat; Repeat itself is not one of them.
+fn command_for(input: &InputEvent) -> Option<MutateCommand> {
+    match input {
+        InputEvent::Hjkl(dir) | InputEvent::Wasd(dir) => Some(MutateCommand::Move(*dir)),
+        InputEvent::Run(dir) => Some(MutateCommand::Run(*dir)),
+        InputEvent::Attack(dir) => Some(MutateCommand::Attack(*dir)),
+        _ => None,
+    }
+}
+
+//What a line typed at the command prompt asks for; None if nothing was typed.
+//Items are numbered as in the snapshot's inventory, and things on the map found by their Coords.
+fn prompt_command(line: &str, snapshot: &RenderSnapshot) -> Result<Option<MutateCommand>, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let command = match words.as_slice() {
+        [] => return Ok(None),
+        ["wait"] => MutateCommand::Wait,
+        ["pickup"] | ["get"] => MutateCommand::PickUp,
+        ["drop", number] => MutateCommand::Drop(carried_item(snapshot, number)?),
+        ["use", number] => MutateCommand::Use(carried_item(snapshot, number)?, None),
+        ["use", number, x, y] => {
+            let item = carried_item(snapshot, number)?;
+            let at = match (x.parse::<u16>(), y.parse::<u16>()) {
+                (Ok(x), Ok(y)) => Coords::new(x, y),
+                _ => return Err("Usage: use <item #> [<x> <y>]".to_string()),
+            };
+            //Aimed at whatever stands there, if anything does; otherwise at the tile.
+            let target = match snapshot.names.iter().find(|(coords, _, _)| *coords == at) {
+                Some((_, entity, _)) => Target::Entity(*entity),
+                None => Target::Tile(at),
+            };
+            MutateCommand::Use(item, Some(target))
+        }
+        ["descend"] | [">"] => MutateCommand::Descend,
+        ["ascend"] | ["<"] => MutateCommand::Ascend,
+        ["open" | "close" | "interact", dir] => match prompt_dir(dir) {
+            Some(dir) => MutateCommand::Interact(dir),
+            None => return Err("Usage: open <n|ne|e|se|s|sw|w|nw>".to_string()),
+        },
+        ["travel", x, y] => match (x.parse::<u16>(), y.parse::<u16>()) {
+            (Ok(x), Ok(y)) => MutateCommand::Travel(Coords::new(x, y)),
+            _ => return Err("Usage: travel <x> <y>".to_string()),
+        },
+        _ => return Err(format!("Unknown command: {}", line.trim())),
+    };
+    Ok(Some(command))
+}
+
+//The inventory, counting from 1, so what is carried can be named by number.
+fn carried_item(snapshot: &RenderSnapshot, number: &str) -> Result<specs::Entity, String> {
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_sub(1))
+        .and_then(|idx| snapshot.inventory.get(idx))
+        .map(|(item, _)| *item)
+        .ok_or_else(|| {
+            let carried: Vec<String> = (1..)
+                .zip(snapshot.inventory.iter())
+                .map(|(number, (_, name))| format!("{} {}", number, name))
+                .collect();
+            if carried.is_empty() {
+                "You are not carrying anything.".to_string()
+            } else {
+                format!("You carry: {}.", carried.join(", "))
+            }
+        })
+}
+
+fn prompt_dir(word: &str) -> Option<Dir> {
+    let dir = match word {
+        "n" => Dir::N,
+        "ne" => Dir::NE,
+        "e" => Dir::E,
+        "se" => Dir::SE,
+        "s" => Dir::S,
+        "sw" => Dir::SW,
+        "w" => Dir::W,
+        "nw" => Dir::NW,
+        _ => return None,
+    };
+    Some(dir)
+}
+
+impl TickLoop for TUIState {
+    ///Waits for whichever comes first: an event in the inbox, or the next redraw.
+    ///Everything already queued by then is handled as one batch, before redrawing at most once.
+    fn tick(&mut self) -> Result<Ticker, Gremlin> {
+        let timeout = self.next_redraw.saturating_duration_since(Instant::now());
+        let first = match self.inbox.recv_timeout(timeout) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => return Err(Gremlin::RecvErr(RecvError)),
+        };
+
+        let batch: Vec<ViewEvent> = first.into_iter().chain(self.inbox.try_iter()).collect();
+        for event in batch {
+            if self.handle(event)? == Ticker::ExitProgram {
+                return Ok(Ticker::ExitProgram);
+            }
+        }
+
+        if Instant::now() >= self.next_redraw {
+            self.redraw()?;
+            self.next_redraw = Instant::now() + REDRAW_INTERVAL;
+        }
+
+        Ok(Ticker::Continue)
+    }
+
+    fn recover(&mut self, e: &Gremlin) {
+        self.message = Some(format!("Something went wrong: {}", e));
+        self.dirty = true;
+    }
+
+    fn fail(&mut self, e: Gremlin) {
+        //If the Controller is gone too, everything is shutting down already.
+        let _ = self.ask(ControlRequest::Shutdown(format!("The tui thread stopped: {}", e)));
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
-    //use super::*;
+    use std::sync::mpsc::{self, Sender};
+
+    use super::*;
+    use crate::common::{view_channel, DeltaNotification, Dir, Rejection};
+
+    fn test_tui() -> (TUIState, Sender<ViewEvent>, Receiver<ModelEvent>, Receiver<ControlRequest>) {
+        let (view_tx, view_rx) = view_channel();
+        let (model_tx, model_rx) = mpsc::sync_channel(1);
+        let (control_tx, control_rx) = mpsc::channel();
+
+        let mut tui = TUIState::new(
+            view_rx,
+            ViewSender::new(view_tx.clone()),
+            model_tx,
+            control_tx,
+            Arc::new(SnapshotSlot::new()),
+            Vec::new(),
+        );
+        tui.out = Box::new(io::sink());
+        tui.size = FALLBACK_SIZE;
+
+        (tui, view_tx, model_rx, control_rx)
+    }
+
+    //What was asked of the Controller since last looked, less the answers given after each input.
+    fn asked(control_rx: &Receiver<ControlRequest>) -> Vec<ControlRequest> {
+        control_rx
+            .try_iter()
+            .filter(|request| !matches!(request, ControlRequest::TextEntry { .. }))
+            .collect()
+    }
+
+    fn sent_command(model_rx: &Receiver<ModelEvent>) -> MutateCommand {
+        match model_rx.try_recv().unwrap() {
+            ModelEvent::Command(request) => request.command,
+            other => panic!("expected a command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tick_handles_whole_batch() {
+        use specs::{Builder, WorldExt};
+
+        let (mut tui, view_tx, model_rx, _control_rx) = test_tui();
+        let mut world: specs::World = WorldExt::new();
+        let entity = world.create_entity().build();
+        let deltas: ViewSender<DeltaNotification> = ViewSender::new(view_tx.clone());
+        let input: ViewSender<InputEvent> = ViewSender::new(view_tx.clone());
+        let states: ViewSender<RunState> = ViewSender::new(view_tx.clone());
+        states.send(RunState::awaiting_input_after(RunState::MapGeneration)).unwrap();
+
+        //Far more than any bounded channel between the two would have held.
+        for _ in 0..100 {
+            deltas.send(DeltaNotification::ComponentRemoved { entity, component: "Position" }).unwrap();
+        }
+        input.send(InputEvent::Hjkl(Dir::N)).unwrap();
+
+        assert_eq!(tui.tick().unwrap(), Ticker::Continue);
+        assert!(tui.inbox.try_recv().is_err());
+        assert_eq!(sent_command(&model_rx), MutateCommand::Move(Dir::N));
+
+        let replies: ViewSender<CommandReply> = ViewSender::new(view_tx);
+        replies
+            .send(CommandReply {
+                outcome: CommandOutcome::Rejected(Rejection::Wall),
+                deltas: Vec::new(),
+            })
+            .unwrap();
+        tui.tick().unwrap();
+        assert_eq!(tui.message.as_deref(), Some("You bump into a wall."));
+
+        input.send(InputEvent::Exit).unwrap();
+        assert_eq!(tui.tick().unwrap(), Ticker::ExitProgram);
+        assert_eq!(sent_command(&model_rx), MutateCommand::Exit);
+    }
+
+    #[test]
+    fn test_tick_wakes_for_redraw() {
+        let (mut tui, _view_tx, _model_rx, _control_rx) = test_tui();
+        tui.tick().unwrap(); //The first redraw is due at once.
+
+        let started = Instant::now();
+        assert_eq!(tui.tick().unwrap(), Ticker::Continue);
+        assert!(started.elapsed() >= REDRAW_INTERVAL / 2);
+        assert!(started.elapsed() < REDRAW_INTERVAL * 10);
+        assert!(!tui.dirty);
+    }
 
     #[test]
-    fn test_0() {}
+    fn test_commands_only_on_players_turn() {
+        let (mut tui, view_tx, model_rx, control_rx) = test_tui();
+        let input: ViewSender<InputEvent> = ViewSender::new(view_tx.clone());
+        let states: ViewSender<RunState> = ViewSender::new(view_tx);
+
+        input.send(InputEvent::Hjkl(Dir::S)).unwrap();
+        tui.tick().unwrap();
+        assert!(model_rx.try_recv().is_err()); //Still in the MainMenu, so that only moved the selection.
+
+        states.send(RunState::awaiting_input_after(RunState::MapGeneration)).unwrap();
+        input.send(InputEvent::Hjkl(Dir::S)).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(asked(&control_rx), vec![ControlRequest::Transition(RunState::GameWorld)]);
+        assert_eq!(sent_command(&model_rx), MutateCommand::Move(Dir::S));
+    }
+
+    #[test]
+    fn test_mouse() {
+        let (mut tui, view_tx, model_rx, control_rx) = test_tui();
+        let input: ViewSender<InputEvent> = ViewSender::new(view_tx.clone());
+        let states: ViewSender<RunState> = ViewSender::new(view_tx);
+        tui.snapshot.map_size = 10;
+        tui.snapshot.tiles = vec!['.'; 100];
+        tui.relayout();
+        states.send(RunState::awaiting_input_after(RunState::MapGeneration)).unwrap();
+
+        input.send(InputEvent::Hover { column: 3, row: 2 }).unwrap();
+        input.send(InputEvent::Click { column: 30, row: 2 }).unwrap(); //Off the map.
+        tui.tick().unwrap();
+        assert_eq!(tui.looking_at, Some(Coords::new(3u16, 2u16)));
+        assert!(model_rx.try_recv().is_err());
+
+        input.send(InputEvent::Click { column: 3, row: 2 }).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(asked(&control_rx), vec![ControlRequest::Transition(RunState::GameWorld)]);
+        assert_eq!(sent_command(&model_rx), MutateCommand::Travel(Coords::new(3u16, 2u16)));
+    }
+
+    #[test]
+    fn test_resize() {
+        let (mut tui, view_tx, _model_rx, _control_rx) = test_tui();
+        let input: ViewSender<InputEvent> = ViewSender::new(view_tx);
+        tui.snapshots.publish(RenderSnapshot {
+            map_size: 10,
+            tiles: vec!['.'; 100],
+            ..RenderSnapshot::default()
+        });
+        tui.tick().unwrap();
+        assert_eq!(tui.layout, Layout::new(80, 24, 10));
+
+        input.send(InputEvent::Resize { columns: 20, rows: 5 }).unwrap();
+        tui.tick().unwrap(); //Redrawn at once, not at the next interval.
+        assert_eq!(tui.layout, None);
+        assert!(!tui.dirty);
+
+        input.send(InputEvent::Resize { columns: 100, rows: 30 }).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(tui.layout.unwrap().log.height, 18);
+        assert!(!tui.dirty);
+    }
+
+    //Everything drawn, kept where the test can read it back.
+    #[derive(Clone, Default)]
+    struct Screen(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for Screen {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Screen {
+        fn take(&self) -> String {
+            String::from_utf8(std::mem::take(&mut *self.0.lock().unwrap())).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_too_small_for_the_map() {
+        let (mut tui, view_tx, _model_rx, _control_rx) = test_tui();
+        assert!(tui.layout.is_some()); //Before any snapshot has come in.
+        let screen = Screen::default();
+        tui.out = Box::new(screen.clone());
+        let input: ViewSender<InputEvent> = ViewSender::new(view_tx.clone());
+        let states: ViewSender<RunState> = ViewSender::new(view_tx);
+        tui.snapshots.publish(RenderSnapshot {
+            map_size: 10,
+            tiles: vec!['.'; 100],
+            ..RenderSnapshot::default()
+        });
+        input.send(InputEvent::Resize { columns: 20, rows: 5 }).unwrap();
+        tui.tick().unwrap();
+
+        //Only the states which draw the map need room for it.
+        let drawn = screen.take();
+        assert!(drawn.contains("New Game"));
+        assert!(!drawn.contains("Terminal too small"));
+
+        states.send(RunState::GameOver).unwrap();
+        tui.next_redraw = Instant::now();
+        tui.tick().unwrap();
+        assert!(screen.take().contains("Game Over"));
+
+        states.send(RunState::awaiting_input_after(RunState::MapGeneration)).unwrap();
+        tui.next_redraw = Instant::now();
+        tui.tick().unwrap();
+        assert!(screen.take().contains("Terminal too small"));
+    }
+
+    #[test]
+    fn test_input_sequence() {
+        let (mut tui, view_tx, model_rx, control_rx) = test_tui();
+        let input: ViewSender<InputEvent> = ViewSender::new(view_tx.clone());
+        let states: ViewSender<RunState> = ViewSender::new(view_tx);
+        states.send(RunState::awaiting_input_after(RunState::MapGeneration)).unwrap();
+
+        input.send(InputEvent::Count(5)).unwrap();
+        input.send(InputEvent::Go).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(tui.sequence.pending().as_deref(), Some("5 go"));
+        assert!(asked(&control_rx).is_empty()); //Nothing is sent until the sequence is complete.
+
+        input.send(InputEvent::Hjkl(Dir::S)).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(asked(&control_rx), vec![ControlRequest::Transition(RunState::GameWorld)]);
+        assert_eq!(
+            sent_command(&model_rx),
+            MutateCommand::Repeat(5, Box::new(MutateCommand::Run(Dir::S)))
+        );
+
+        input.send(InputEvent::Fight).unwrap();
+        input.send(InputEvent::Cancel).unwrap();
+        input.send(InputEvent::Wasd(Dir::E)).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(sent_command(&model_rx), MutateCommand::Move(Dir::E));
+
+        //Leaving the player's turn throws away a half-typed sequence.
+        input.send(InputEvent::Count(3)).unwrap();
+        states.send(RunState::Tui).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(tui.sequence.pending(), None);
+    }
+
+    #[test]
+    fn test_prompt() {
+        let (mut tui, view_tx, model_rx, control_rx) = test_tui();
+        let input: ViewSender<InputEvent> = ViewSender::new(view_tx.clone());
+        let states: ViewSender<RunState> = ViewSender::new(view_tx);
+        states.send(RunState::awaiting_input_after(RunState::MapGeneration)).unwrap();
+
+        input.send(InputEvent::Prompt).unwrap();
+        for c in "wiat".chars() {
+            input.send(InputEvent::Char(c)).unwrap();
+        }
+        tui.tick().unwrap();
+        assert!(tui.prompting);
+        assert_eq!(control_rx.try_iter().last(), Some(ControlRequest::TextEntry { on: true, inputs: 5 }));
+
+        input.send(InputEvent::Confirm).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(tui.message.as_deref(), Some("Unknown command: wiat"));
+        assert_eq!(control_rx.try_iter().last(), Some(ControlRequest::TextEntry { on: false, inputs: 6 }));
+        assert!(model_rx.try_recv().is_err());
+
+        //The last command comes back with Up, to be fixed.
+        input.send(InputEvent::Prompt).unwrap();
+        input.send(InputEvent::Hjkl(Dir::N)).unwrap();
+        input.send(InputEvent::Hjkl(Dir::W)).unwrap();
+        input.send(InputEvent::Backspace).unwrap();
+        input.send(InputEvent::Backspace).unwrap();
+        input.send(InputEvent::Char('a')).unwrap();
+        input.send(InputEvent::Char('i')).unwrap();
+        input.send(InputEvent::Confirm).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(sent_command(&model_rx), MutateCommand::Wait);
+
+        //Leaving the player's turn closes the prompt.
+        states.send(RunState::awaiting_input_after(RunState::GameWorld)).unwrap();
+        input.send(InputEvent::Prompt).unwrap();
+        input.send(InputEvent::Char('>')).unwrap();
+        states.send(RunState::Tui).unwrap();
+        tui.tick().unwrap();
+        assert!(!tui.prompting);
+        assert_eq!(control_rx.try_iter().last(), Some(ControlRequest::TextEntry { on: false, inputs: 16 }));
+
+        let none = RenderSnapshot::default();
+        assert_eq!(prompt_command(" travel 3 4 ", &none), Ok(Some(MutateCommand::Travel(Coords::new(3u16, 4u16)))));
+        assert_eq!(prompt_command("travel x 4", &none), Err("Usage: travel <x> <y>".to_string()));
+        assert_eq!(prompt_command("", &none), Ok(None));
+        assert_eq!(prompt_command("open ne", &none), Ok(Some(MutateCommand::Interact(Dir::NE))));
+        assert_eq!(prompt_command("drop 1", &none), Err("You are not carrying anything.".to_string()));
+    }
+
+    #[test]
+    fn test_prompt_items() {
+        use specs::{Builder, WorldExt};
+
+        let mut world: specs::World = WorldExt::new();
+        let (potion, scroll, goblin) = (
+            world.create_entity().build(),
+            world.create_entity().build(),
+            world.create_entity().build(),
+        );
+        let snapshot = RenderSnapshot {
+            names: vec![(Coords::new(4u16, 4u16), goblin, "goblin".to_string())],
+            inventory: vec![(potion, "potion".to_string()), (scroll, "scroll".to_string())],
+            ..RenderSnapshot::default()
+        };
+
+        assert_eq!(prompt_command("drop 2", &snapshot), Ok(Some(MutateCommand::Drop(scroll))));
+        assert_eq!(prompt_command("use 1", &snapshot), Ok(Some(MutateCommand::Use(potion, None))));
+        assert_eq!(
+            prompt_command("use 2 4 4", &snapshot),
+            Ok(Some(MutateCommand::Use(scroll, Some(Target::Entity(goblin)))))
+        );
+        assert_eq!(
+            prompt_command("use 2 5 4", &snapshot),
+            Ok(Some(MutateCommand::Use(scroll, Some(Target::Tile(Coords::new(5u16, 4u16))))))
+        );
+        assert_eq!(prompt_command("drop 3", &snapshot), Err("You carry: 1 potion, 2 scroll.".to_string()));
+    }
+
+    #[test]
+    fn test_main_menu() {
+        let (mut tui, view_tx, model_rx, control_rx) = test_tui();
+        let input: ViewSender<InputEvent> = ViewSender::new(view_tx);
+
+        //New Game asks for a seed, then tells the GameWorld before asking the Controller to start.
+        //The seed is typed as text, so the Controller is asked to stop looking up keys until it is.
+        //Every input is answered, so the Controller knows which keys came after the change.
+        input.send(InputEvent::Confirm).unwrap();
+        input.send(InputEvent::Confirm).unwrap();
+        tui.tick().unwrap();
+        assert!(matches!(model_rx.try_recv(), Ok(ModelEvent::NewGame { .. })));
+        assert_eq!(
+            control_rx.try_iter().collect::<Vec<_>>(),
+            vec![
+                ControlRequest::TextEntry { on: true, inputs: 1 },
+                ControlRequest::Transition(RunState::PreRun),
+                ControlRequest::TextEntry { on: false, inputs: 2 }
+            ]
+        );
+
+        input.send(InputEvent::Hjkl(Dir::N)).unwrap();
+        input.send(InputEvent::Confirm).unwrap();
+        tui.tick().unwrap();
+        assert_eq!(asked(&control_rx), vec![ControlRequest::Quit]);
+    }
+
+    #[test]
+    fn test_recover_and_fail() {
+        let (mut tui, _view_tx, _model_rx, control_rx) = test_tui();
+
+        tui.recover(&Gremlin::OutOfMapBounds);
+        assert_eq!(tui.message.as_deref(), Some("Something went wrong: out of map bounds"));
+
+        tui.fail(Gremlin::RecvErr(RecvError));
+        assert_eq!(
+            control_rx.try_recv().unwrap(),
+            ControlRequest::Shutdown("The tui thread stopped: receiving on a closed channel".to_string())
+        );
+    }
 }
@@ -43,6 +43,7 @@ pub trait Observer: Send + Sync {
 }
 
 pub trait Observable : Send + Sync {
+    #[allow(clippy::declare_interior_mutable_const)]
     const ID_GENERATOR: IdGenerator;
     type Observers: IntoIterator;
 
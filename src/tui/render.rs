@@ -0,0 +1,271 @@
+//Jerome M. St.Martin
+//June 26, 2022
+
+//-----------------------------------------------------------------------------
+//---------------------- Draws a RenderSnapshot to a Terminal -----------------
+//-----------------------------------------------------------------------------
+
+/* The screen is split into three panes, recomputed whenever the terminal is
+ * resized or the map changes size:
+ *
+ *     +--------+
+ *     |  map   |       The map in the top left corner, one tile per cell.
+ *     +--------+----+
+ *     | status      |  The look line, then the message line.
+ *     +-------------+
+ *     | log         |  As many of the newest log entries as fit.
+ *     +-------------+
+ *
+ * Anything smaller than that, plus MIN_LOG_ROWS, gets a "too small" screen.
+ */
+
+use std::io::Write;
+
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::Print,
+    terminal::{Clear, ClearType},
+};
+
+use crate::common::{Coords, RenderSnapshot};
+use crate::error::Gremlin;
+
+//Wide enough for the title screen and most messages, whatever the map size.
+const MIN_COLUMNS: u16 = 40;
+const STATUS_ROWS: u16 = 2;
+const MIN_LOG_ROWS: u16 = 1;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(super) struct Pane {
+    pub column: u16,
+    pub row: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Pane {
+    fn contains(&self, column: u16, row: u16) -> bool {
+        column >= self.column
+            && column < self.column + self.width
+            && row >= self.row
+            && row < self.row + self.height
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(super) struct Layout {
+    pub map: Pane,
+    pub status: Pane,
+    pub log: Pane,
+}
+
+impl Layout {
+    ///None if the terminal is too small to fit every pane.
+    pub(super) fn new(columns: u16, rows: u16, map_size: u16) -> Option<Layout> {
+        let (min_columns, min_rows) = Layout::minimum(map_size);
+        if columns < min_columns || rows < min_rows {
+            return None;
+        }
+
+        Some(Layout {
+            map: Pane { column: 0, row: 0, width: map_size, height: map_size },
+            status: Pane { column: 0, row: map_size, width: columns, height: STATUS_ROWS },
+            log: Pane {
+                column: 0,
+                row: map_size + STATUS_ROWS,
+                width: columns,
+                height: rows - map_size - STATUS_ROWS,
+            },
+        })
+    }
+
+    ///(columns, rows)
+    pub(super) fn minimum(map_size: u16) -> (u16, u16) {
+        (MIN_COLUMNS.max(map_size), map_size + STATUS_ROWS + MIN_LOG_ROWS)
+    }
+
+    ///The map tile drawn at a position on the screen, if any is.
+    pub(super) fn map_coords(&self, column: u16, row: u16) -> Option<Coords> {
+        if self.map.contains(column, row) {
+            Some(Coords::new(column - self.map.column, row - self.map.row))
+        } else {
+            None
+        }
+    }
+}
+
+pub(super) fn draw<W: Write>(
+    out: &mut W,
+    layout: &Layout,
+    snapshot: &RenderSnapshot,
+    look: Option<&str>,
+    message: Option<&str>,
+) -> Result<(), Gremlin> {
+    queue!(out, Clear(ClearType::All))?;
+
+    let size = snapshot.map_size as usize;
+    for y in 0..size {
+        let mut row: Vec<char> = snapshot.tiles[y * size..(y + 1) * size].to_vec();
+        for (coords, glyph) in snapshot.entities.iter().filter(|(coords, _)| coords.y as usize == y) {
+            row[coords.x as usize] = *glyph;
+        }
+
+        let at = MoveTo(layout.map.column, layout.map.row + y as u16);
+        queue!(out, at, Print(row.into_iter().collect::<String>()))?;
+    }
+
+    let status = &layout.status;
+    for (offset, line) in (0..).zip([look, message]) {
+        if let Some(line) = line {
+            queue!(out, MoveTo(status.column, status.row + offset), Print(fit(line, status.width)))?;
+        }
+    }
+
+    let log = &layout.log;
+    let newest = snapshot.log.len().saturating_sub(log.height as usize);
+    for (offset, entry) in (0..).zip(snapshot.log[newest..].iter()) {
+        queue!(out, MoveTo(log.column, log.row + offset), Print(fit(entry, log.width)))?;
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+///What is on a tile, for the look line.
+pub(super) fn describe(snapshot: &RenderSnapshot, at: Coords) -> String {
+    let here: Vec<&str> = snapshot
+        .names
+        .iter()
+        .filter(|(coords, _, _)| *coords == at)
+        .map(|(_, _, name)| name.as_str())
+        .collect();
+    if !here.is_empty() {
+        return format!("You see: {}.", here.join(", "));
+    }
+
+    let idx = at.y as usize * snapshot.map_size as usize + at.x as usize;
+    match snapshot.tiles.get(idx) {
+        Some('.') => "You see: the floor.".to_string(),
+        _ => "You see: a wall.".to_string(),
+    }
+}
+
+///Lines of text in place of the map, e.g. for a title screen.
+pub(super) fn draw_screen<W: Write>(out: &mut W, lines: &[&str]) -> Result<(), Gremlin> {
+    queue!(out, Clear(ClearType::All))?;
+
+    for (y, line) in (0..).zip(lines.iter()) {
+        queue!(out, MoveTo(0, y), Print(line))?;
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+///In place of everything else, until the terminal is made big enough.
+pub(super) fn draw_too_small<W: Write>(out: &mut W, columns: u16, rows: u16, map_size: u16) -> Result<(), Gremlin> {
+    let (min_columns, min_rows) = Layout::minimum(map_size);
+    let lines = [
+        "Terminal too small".to_string(),
+        format!("Need {}x{}, have {}x{}.", min_columns, min_rows, columns, rows),
+    ];
+
+    queue!(out, Clear(ClearType::All))?;
+    for (y, line) in (0..rows).zip(lines.iter()) {
+        queue!(out, MoveTo(0, y), Print(fit(line, columns)))?;
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+//Cut short to fit the width of a pane, so nothing wraps onto the next one.
+fn fit(line: &str, width: u16) -> String {
+    line.chars().take(width as usize).collect()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_draw() {
+        let snapshot = RenderSnapshot {
+            map_size: 2,
+            tiles: vec!['#', '.', '.', '#'],
+            entities: vec![(Coords::new(0u16, 1u16), '@')],
+            log: vec!["Welcome to GoblinRL!".to_string()],
+            ..RenderSnapshot::default()
+        };
+        let layout = Layout::new(80, 24, 2).unwrap();
+
+        let mut out: Vec<u8> = Vec::new();
+        draw(&mut out, &layout, &snapshot, Some("You see: you."), Some("You bump into a wall.")).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("#."));
+        assert!(out.contains("@#"));
+        assert!(out.contains("You bump into a wall."));
+        assert!(out.contains("Welcome to GoblinRL!"));
+        assert!(out.contains("You see: you."));
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout = Layout::new(80, 24, 10).unwrap();
+        assert_eq!(layout.status, Pane { column: 0, row: 10, width: 80, height: 2 });
+        assert_eq!(layout.log, Pane { column: 0, row: 12, width: 80, height: 12 });
+
+        //Just big enough, then one short either way.
+        assert_eq!(Layout::new(40, 13, 10).unwrap().log.height, 1);
+        assert!(Layout::new(39, 13, 10).is_none());
+        assert!(Layout::new(40, 12, 10).is_none());
+        assert_eq!(Layout::minimum(60), (60, 63));
+
+        //Only the newest entries that fit are drawn, cut to the width.
+        let snapshot = RenderSnapshot {
+            map_size: 10,
+            tiles: vec!['.'; 100],
+            log: vec!["oldest".to_string(), format!("newest{}", "!".repeat(40))],
+            ..RenderSnapshot::default()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        draw(&mut out, &Layout::new(40, 13, 10).unwrap(), &snapshot, None, None).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.contains("oldest"));
+        assert!(out.contains(&format!("newest{}", "!".repeat(34))));
+        assert!(!out.contains(&format!("newest{}", "!".repeat(35))));
+
+        let mut out: Vec<u8> = Vec::new();
+        draw_too_small(&mut out, 30, 5, 10).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Terminal too small"));
+        assert!(out.contains("Need 40x13, have 30x5."));
+    }
+
+    #[test]
+    fn test_look() {
+        use specs::{Builder, WorldExt};
+
+        let mut world: specs::World = WorldExt::new();
+        let (you, potion) = (world.create_entity().build(), world.create_entity().build());
+        let snapshot = RenderSnapshot {
+            map_size: 2,
+            tiles: vec!['#', '.', '.', '#'],
+            names: vec![
+                (Coords::new(0u16, 1u16), you, "you".to_string()),
+                (Coords::new(0u16, 1u16), potion, "potion".to_string()),
+            ],
+            ..RenderSnapshot::default()
+        };
+        let layout = Layout::new(80, 24, 2).unwrap();
+
+        assert_eq!(layout.map_coords(1, 0), Some(Coords::new(1u16, 0u16)));
+        assert_eq!(layout.map_coords(2, 0), None);
+        assert_eq!(describe(&snapshot, Coords::new(0u16, 1u16)), "You see: you, potion.");
+        assert_eq!(describe(&snapshot, Coords::new(1u16, 0u16)), "You see: the floor.");
+        assert_eq!(describe(&snapshot, Coords::new(0u16, 0u16)), "You see: a wall.");
+    }
+}
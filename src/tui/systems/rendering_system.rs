@@ -15,23 +15,13 @@ impl <'a> System<'a> for RenderingSystem {
                         ReadStorage<'a, Renderable>,
                         ReadStorage<'a, Position> );
 
-    /* Example run() call:
-     * let rs = RenderingSystem {};
-     * let ecs_borrow: &specs::World = &self ecs;
-     * let entities = ecs_ap
-     *                .req_access(AccessKey::Entity)
-     *                .read_storage<Entity>(&ecs_borrow);
-     * 
-     * let renderables = ecs_ap
-     *                   .req_access(AccessKey::Renderable)
-     *                   .read_storage<Renderable>(&ecs_borrow);
+    /* Example run() call, from any thread holding an Arc<ECSAccessPoint>:
+     * let mut rs = RenderingSystem {};
+     * ecs_ap.run_system(&mut rs);
      *
-     * let positions = ecs_ap
-     *                 .req_access(AccessKey::Position)
-     *                 .read_storage<Position>(&ecs_borrow);
-     *
-     * let rs_data = (entities, renderables, positions);
-     * rs.run(rs_data);
+     * run_system() reads the locks it needs from this SystemData
+     * (Entities, Renderable and Position), acquires them all at once,
+     * then fetches the SystemData and calls run().
      */
 
     fn run(&mut self, data: Self::SystemData) {
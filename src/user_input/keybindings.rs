@@ -0,0 +1,552 @@
+//Jerome M. St.Martin
+//June 29, 2022
+
+//-----------------------------------------------------------------------------
+//------------------------------ Keybindings ----------------------------------
+//-----------------------------------------------------------------------------
+
+/* A keybindings file is JSON, e.g.:
+ *
+ * {
+ *     "layouts": ["vi", "numpad"],
+ *     "bindings": [
+ *         { "key": "Ctrl+q", "event": "Exit" },
+ *         { "key": "m", "event": "Menu" },
+ *         { "key": "Shift+Up", "event": { "Hjkl": "N" } }
+ *     ]
+ * }
+ *
+ * "layouts" are built in, see LAYOUTS, and default to DEFAULT_LAYOUTS.
+ * The core bindings (Enter, Esc, Tab, F-keys, Ctrl+c, g, F, :...) are always on.
+ * In text entry, keys are mostly typed as they are instead, see UserInput.
+ * "bindings" are applied last, so they replace whatever a layout bound.
+ * Keys are written Mod+Mod+Key, with the modifiers Ctrl, Alt and Shift.
+ */
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::common::{Dir, InputEvent};
+use crate::error::Gremlin;
+
+//Looked for next to wherever the game was launched from, if no file was given.
+pub const DEFAULT_KEYBINDINGS_PATH: &str = "keybindings.json";
+
+//Together, what was bound before keybindings could be configured, and more.
+const DEFAULT_LAYOUTS: [&str; 4] = ["arrows", "vi", "wasd", "counts"];
+
+//counts and numpad both want the digits, so only one of the two can be used.
+const LAYOUTS: [&str; 5] = ["arrows", "vi", "wasd", "numpad", "counts"];
+
+//help() wraps its lines to fit the narrowest terminal the TUI expects.
+const HELP_WIDTH: usize = 78;
+
+//Whether an InputEvent belongs under one line of help().
+type HelpFilter = fn(&InputEvent) -> bool;
+
+//What help() lists, in order, and which InputEvents belong under each.
+const HELP: [(&str, HelpFilter); 10] = [
+    ("Move", |event| matches!(event, InputEvent::Hjkl(_) | InputEvent::Wasd(_))),
+    ("Run", |event| matches!(event, InputEvent::Run(_))),
+    ("Attack", |event| matches!(event, InputEvent::Attack(_))),
+    ("Run (then a direction)", |event| *event == InputEvent::Go),
+    ("Fight (then a direction)", |event| *event == InputEvent::Fight),
+    ("Repeat (count, then a command)", |event| matches!(event, InputEvent::Count(_))),
+    ("Command prompt", |event| *event == InputEvent::Prompt),
+    ("Menu", |event| *event == InputEvent::Menu),
+    ("Back", |event| *event == InputEvent::Cancel),
+    ("Quit at any time", |event| *event == InputEvent::Exit),
+];
+
+///A key, with the modifiers held down while it was pressed.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct KeyChord {
+    code: KeyCode,
+    mods: KeyModifiers,
+}
+
+impl KeyChord {
+    ///Shift is folded into the character, so 'K' and Shift+k are the same chord.
+    pub fn new(code: KeyCode, mods: KeyModifiers) -> Self {
+        match code {
+            KeyCode::Char(c) if mods.contains(KeyModifiers::SHIFT) => KeyChord {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                mods: mods - KeyModifiers::SHIFT,
+            },
+            KeyCode::BackTab => KeyChord { code, mods: mods - KeyModifiers::SHIFT },
+            _ => KeyChord { code, mods },
+        }
+    }
+
+    fn plain(code: KeyCode) -> Self {
+        KeyChord::new(code, KeyModifiers::NONE)
+    }
+
+    #[cfg(test)]
+    fn ch(c: char) -> Self {
+        KeyChord::plain(KeyCode::Char(c))
+    }
+
+    ///e.g. "k", "Shift+Up", "Ctrl+Alt+F5", "Space", "Plus".
+    pub fn parse(text: &str) -> Result<Self, Gremlin> {
+        let unknown = |what: &str| Gremlin::InvalidKeybindings(format!("unknown {} in key \"{}\"", what, text));
+
+        let mut parts: Vec<&str> = text.split('+').collect();
+        let key = parts.pop().filter(|key| !key.is_empty()).ok_or_else(|| unknown("key"))?;
+
+        let mut mods = KeyModifiers::NONE;
+        for part in parts {
+            mods |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(unknown(&format!("modifier \"{}\"", part))),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "plus" => KeyCode::Char('+'),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(unknown(&format!("key name \"{}\"", key))),
+                },
+            },
+        };
+
+        Ok(KeyChord::new(code, mods))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.mods.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.mods.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.mods.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char('+') => write!(f, "Plus"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+//As written in the file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeybindingsFile {
+    #[serde(default = "default_layouts")]
+    layouts: Vec<String>,
+    #[serde(default)]
+    bindings: Vec<BindingEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingEntry {
+    key: String,
+    event: InputEvent,
+}
+
+fn default_layouts() -> Vec<String> {
+    DEFAULT_LAYOUTS.iter().map(|name| name.to_string()).collect()
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct Keybindings {
+    map: HashMap<KeyChord, InputEvent>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Keybindings::from_layouts(&DEFAULT_LAYOUTS).unwrap() //built-in, so never conflicting
+    }
+}
+
+impl Keybindings {
+    ///The core bindings, plus the named layouts.
+    pub fn from_layouts<S: AsRef<str>>(names: &[S]) -> Result<Self, Gremlin> {
+        let mut layouts = vec![("core", core_layout())];
+        for name in names {
+            layouts.push((name.as_ref(), layout(name.as_ref())?));
+        }
+        Keybindings::merge(layouts)
+    }
+
+    //Two layouts binding the same key to different InputEvents is an error.
+    fn merge(layouts: Vec<(&str, Vec<(KeyChord, InputEvent)>)>) -> Result<Self, Gremlin> {
+        let mut map: HashMap<KeyChord, InputEvent> = HashMap::new();
+        let mut bound_by: HashMap<KeyChord, &str> = HashMap::new();
+
+        for (name, layout) in layouts {
+            for (chord, event) in layout {
+                match map.get(&chord) {
+                    Some(earlier) if *earlier != event => {
+                        return Err(Gremlin::InvalidKeybindings(format!(
+                            "layouts \"{}\" and \"{}\" both bind {}, to {:?} and {:?}",
+                            bound_by[&chord], name, chord, earlier, event
+                        )));
+                    }
+                    Some(_) => {}
+                    None => {
+                        map.insert(chord, event);
+                        bound_by.insert(chord, name);
+                    }
+                }
+            }
+        }
+
+        Ok(Keybindings { map })
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, Gremlin> {
+        let file: KeybindingsFile = serde_json::from_str(text)
+            .map_err(|e| Gremlin::InvalidKeybindings(e.to_string()))?;
+        let mut keybindings = Keybindings::from_layouts(&file.layouts)?;
+
+        let mut rebound: HashMap<KeyChord, InputEvent> = HashMap::new();
+        for entry in file.bindings {
+            let chord = KeyChord::parse(&entry.key)?;
+            if let Some(earlier) = rebound.get(&chord) {
+                return Err(Gremlin::InvalidKeybindings(format!(
+                    "{} is bound twice, to {:?} and {:?}",
+                    chord, earlier, entry.event
+                )));
+            }
+            rebound.insert(chord, entry.event.clone());
+            keybindings.map.insert(chord, entry.event);
+        }
+
+        if !keybindings.map.values().any(|event| *event == InputEvent::Exit) {
+            return Err(Gremlin::InvalidKeybindings("no key is bound to Exit".to_string()));
+        }
+
+        Ok(keybindings)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Gremlin> {
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            Gremlin::InvalidKeybindings(format!("cannot read {}: {}", path.as_ref().display(), e))
+        })?;
+        Keybindings::from_json(&text).map_err(|e| match e {
+            Gremlin::InvalidKeybindings(why) => {
+                Gremlin::InvalidKeybindings(format!("{}: {}", path.as_ref().display(), why))
+            }
+            e => e,
+        })
+    }
+
+    ///The given file, else DEFAULT_KEYBINDINGS_PATH if there is one, else the default.
+    pub fn find(path: Option<&Path>) -> Result<Self, Gremlin> {
+        match path {
+            Some(path) => Keybindings::load(path),
+            None if Path::new(DEFAULT_KEYBINDINGS_PATH).exists() => Keybindings::load(DEFAULT_KEYBINDINGS_PATH),
+            None => Ok(Keybindings::default()),
+        }
+    }
+
+    ///What is bound to what, for the Options screen: a line or more for each kind
+    ///of action in HELP which anything is bound to.
+    pub fn help(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (label, belongs) in HELP {
+            let mut bound: Vec<(usize, &KeyChord)> = self
+                .map
+                .iter()
+                .filter(|(_, event)| belongs(event))
+                .map(|(chord, event)| (help_rank(event), chord))
+                .collect();
+            if bound.is_empty() {
+                continue;
+            }
+            //Directions in Dir::ALL order, then digits in order; plain keys before named ones.
+            bound.sort_by_key(|(rank, chord)| (*rank, chord.mods.bits(), chord.to_string().len(), chord.to_string()));
+
+            let mut line = format!("{}:", label);
+            for (_, chord) in bound {
+                let key = chord.to_string();
+                if line.len() + 1 + key.len() > HELP_WIDTH {
+                    lines.push(line);
+                    line = "   ".to_string();
+                }
+                line.push(' ');
+                line.push_str(&key);
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    ///Null if the key is not bound.
+    pub fn translate(&self, key_event: KeyEvent) -> InputEvent {
+        self.map
+            .get(&KeyChord::new(key_event.code, key_event.modifiers))
+            .cloned()
+            .unwrap_or(InputEvent::Null)
+    }
+}
+
+fn help_rank(event: &InputEvent) -> usize {
+    match event {
+        InputEvent::Hjkl(dir) | InputEvent::Wasd(dir) | InputEvent::Run(dir) | InputEvent::Attack(dir) => {
+            Dir::ALL.iter().position(|d| d == dir).unwrap_or(0)
+        }
+        InputEvent::Count(digit) => *digit as usize,
+        _ => 0,
+    }
+}
+
+fn core_layout() -> Vec<(KeyChord, InputEvent)> {
+    let mut core = vec![
+        (KeyChord::plain(KeyCode::Enter), InputEvent::Confirm),
+        (KeyChord::plain(KeyCode::Esc), InputEvent::Cancel),
+        (KeyChord::plain(KeyCode::Tab), InputEvent::Tab),
+        (KeyChord::plain(KeyCode::BackTab), InputEvent::BackTab),
+        (KeyChord::plain(KeyCode::Backspace), InputEvent::Delete),
+        (KeyChord::plain(KeyCode::Delete), InputEvent::Delete),
+        (KeyChord::new(KeyCode::Char('c'), KeyModifiers::CONTROL), InputEvent::Exit),
+        (KeyChord::plain(KeyCode::Char('g')), InputEvent::Go),
+        (KeyChord::plain(KeyCode::Char('F')), InputEvent::Fight),
+        (KeyChord::plain(KeyCode::Char(':')), InputEvent::Prompt),
+    ];
+    core.extend((1..=12).map(|n| (KeyChord::plain(KeyCode::F(n)), InputEvent::Menu)));
+    core
+}
+
+//Every layout moves in eight directions, except arrows, which only has four keys.
+fn layout(name: &str) -> Result<Vec<(KeyChord, InputEvent)>, Gremlin> {
+    use KeyCode::{Char, Down, Left, Right, Up};
+
+    let bindings = match name {
+        "arrows" => directions(
+            &[(Up, Dir::N), (Down, Dir::S), (Left, Dir::W), (Right, Dir::E)],
+            InputEvent::Hjkl,
+            true,
+        ),
+        "vi" => directions(
+            &[
+                (Char('k'), Dir::N),
+                (Char('u'), Dir::NE),
+                (Char('l'), Dir::E),
+                (Char('n'), Dir::SE),
+                (Char('j'), Dir::S),
+                (Char('b'), Dir::SW),
+                (Char('h'), Dir::W),
+                (Char('y'), Dir::NW),
+            ],
+            InputEvent::Hjkl,
+            true,
+        ),
+        "wasd" => directions(
+            &[
+                (Char('w'), Dir::N),
+                (Char('e'), Dir::NE),
+                (Char('d'), Dir::E),
+                (Char('c'), Dir::SE),
+                (Char('s'), Dir::S),
+                (Char('z'), Dir::SW),
+                (Char('a'), Dir::W),
+                (Char('q'), Dir::NW),
+            ],
+            InputEvent::Wasd,
+            true,
+        ),
+        //Shift turns the digits into other characters, so there is no running here.
+        "numpad" => directions(
+            &[
+                (Char('8'), Dir::N),
+                (Char('9'), Dir::NE),
+                (Char('6'), Dir::E),
+                (Char('3'), Dir::SE),
+                (Char('2'), Dir::S),
+                (Char('1'), Dir::SW),
+                (Char('4'), Dir::W),
+                (Char('7'), Dir::NW),
+            ],
+            InputEvent::Hjkl,
+            false,
+        ),
+        //Count prefixes, as in 5j.
+        "counts" => (0..=9)
+            .map(|digit| (KeyChord::plain(Char((b'0' + digit) as char)), InputEvent::Count(digit)))
+            .collect(),
+        _ => {
+            return Err(Gremlin::InvalidKeybindings(format!(
+                "unknown layout \"{}\"; the layouts are {}",
+                name,
+                LAYOUTS.join(", ")
+            )))
+        }
+    };
+    Ok(bindings)
+}
+
+//Each key steps; with Shift it runs, and with Alt it attacks without stepping.
+//Alt rather than Ctrl, since Ctrl+c is taken, and Ctrl+h is Backspace in many terminals.
+fn directions(keys: &[(KeyCode, Dir)], step: fn(Dir) -> InputEvent, run: bool) -> Vec<(KeyChord, InputEvent)> {
+    let mut bindings = Vec::new();
+    for (code, dir) in keys.iter().copied() {
+        bindings.push((KeyChord::plain(code), step(dir)));
+        if run {
+            bindings.push((KeyChord::new(code, KeyModifiers::SHIFT), InputEvent::Run(dir)));
+        }
+        bindings.push((KeyChord::new(code, KeyModifiers::ALT), InputEvent::Attack(dir)));
+    }
+    bindings
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn key(code: KeyCode, mods: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, mods)
+    }
+
+    #[test]
+    fn test_default_layouts() {
+        let keys = Keybindings::default();
+        assert_eq!(keys.translate(key(KeyCode::Char('q'), KeyModifiers::NONE)), InputEvent::Wasd(Dir::NW));
+        assert_eq!(keys.translate(key(KeyCode::Char('k'), KeyModifiers::NONE)), InputEvent::Hjkl(Dir::N));
+        assert_eq!(keys.translate(key(KeyCode::Left, KeyModifiers::NONE)), InputEvent::Hjkl(Dir::W));
+        assert_eq!(keys.translate(key(KeyCode::F(3), KeyModifiers::NONE)), InputEvent::Menu);
+        assert_eq!(keys.translate(key(KeyCode::Char('c'), KeyModifiers::CONTROL)), InputEvent::Exit);
+        assert_eq!(keys.translate(key(KeyCode::BackTab, KeyModifiers::SHIFT)), InputEvent::BackTab);
+        assert_eq!(keys.translate(key(KeyCode::Char('x'), KeyModifiers::NONE)), InputEvent::Null);
+        assert_eq!(Keybindings::from_json("{}").unwrap(), keys);
+
+        assert_eq!(keys.translate(key(KeyCode::Char('y'), KeyModifiers::NONE)), InputEvent::Hjkl(Dir::NW));
+        assert_eq!(keys.translate(key(KeyCode::Char('n'), KeyModifiers::NONE)), InputEvent::Hjkl(Dir::SE));
+        assert_eq!(keys.translate(key(KeyCode::Char('K'), KeyModifiers::SHIFT)), InputEvent::Run(Dir::N));
+        assert_eq!(keys.translate(key(KeyCode::Char('Z'), KeyModifiers::NONE)), InputEvent::Run(Dir::SW));
+        assert_eq!(keys.translate(key(KeyCode::Left, KeyModifiers::SHIFT)), InputEvent::Run(Dir::W));
+        assert_eq!(keys.translate(key(KeyCode::Char('b'), KeyModifiers::ALT)), InputEvent::Attack(Dir::SW));
+        assert_eq!(keys.translate(key(KeyCode::Char('5'), KeyModifiers::NONE)), InputEvent::Count(5));
+        assert_eq!(keys.translate(key(KeyCode::Char('f'), KeyModifiers::SHIFT)), InputEvent::Fight);
+        assert_eq!(keys.translate(key(KeyCode::Char(':'), KeyModifiers::SHIFT)), InputEvent::Prompt);
+    }
+
+    #[test]
+    fn test_help() {
+        let help = Keybindings::from_layouts(&["vi"]).unwrap().help();
+        assert_eq!(help[0], "Move: k u l n j b h y");
+        assert_eq!(help[1], "Run: K U L N J B H Y");
+        assert!(help.contains(&"Menu: F1 F2 F3 F4 F5 F6 F7 F8 F9 F10 F11 F12".to_string()));
+        assert!(help.contains(&"Quit at any time: Ctrl+c".to_string()));
+        assert!(help.contains(&"Run (then a direction): g".to_string()));
+        assert!(!help.iter().any(|line| line.starts_with("Repeat")));
+
+        let rebound = Keybindings::from_json(r#"{ "bindings": [{ "key": "Ctrl+q", "event": "Exit" }] }"#).unwrap();
+        let help = rebound.help();
+        assert!(help.iter().all(|line| line.len() <= HELP_WIDTH));
+        assert!(help.contains(&"Quit at any time: Ctrl+c Ctrl+q".to_string()));
+        let moves: Vec<&String> = help.iter().skip_while(|line| !line.starts_with("Move:")).take(2).collect();
+        assert!(moves[0].starts_with("Move: k w Up e u d l Right"));
+    }
+
+    #[test]
+    fn test_parse_chord() {
+        assert_eq!(KeyChord::parse("K").unwrap(), KeyChord::parse("Shift+k").unwrap());
+        assert_eq!(
+            KeyChord::parse("ctrl+Alt+F5").unwrap(),
+            KeyChord::new(KeyCode::F(5), KeyModifiers::CONTROL | KeyModifiers::ALT)
+        );
+        assert_eq!(KeyChord::parse("Space").unwrap(), KeyChord::ch(' '));
+        assert_eq!(KeyChord::parse("Ctrl+Plus").unwrap().to_string(), "Ctrl+Plus");
+        assert!(KeyChord::parse("Hyper+k").is_err());
+        assert!(KeyChord::parse("F13").is_err());
+        assert!(KeyChord::parse("Ctrl+").is_err());
+    }
+
+    #[test]
+    fn test_from_json() {
+        let keys = Keybindings::from_json(
+            r#"{
+                "layouts": ["vi", "numpad"],
+                "bindings": [
+                    { "key": "Ctrl+q", "event": "Exit" },
+                    { "key": "Shift+Up", "event": { "Hjkl": "N" } },
+                    { "key": "k", "event": "Menu" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(keys.translate(key(KeyCode::Char('q'), KeyModifiers::CONTROL)), InputEvent::Exit);
+        assert_eq!(keys.translate(key(KeyCode::Up, KeyModifiers::SHIFT)), InputEvent::Hjkl(Dir::N));
+        assert_eq!(keys.translate(key(KeyCode::Char('k'), KeyModifiers::NONE)), InputEvent::Menu);
+        assert_eq!(keys.translate(key(KeyCode::Char('7'), KeyModifiers::NONE)), InputEvent::Hjkl(Dir::NW));
+        assert_eq!(keys.translate(key(KeyCode::Char('w'), KeyModifiers::NONE)), InputEvent::Null);
+    }
+
+    #[test]
+    fn test_errors() {
+        let why = |json: &str| match Keybindings::from_json(json) {
+            Err(Gremlin::InvalidKeybindings(why)) => why,
+            other => panic!("expected InvalidKeybindings, got {:?}", other),
+        };
+
+        assert!(why(r#"{"layouts": ["dvorak"]}"#).contains("unknown layout \"dvorak\""));
+        assert!(why(r#"{"bindings": [{"key": "Meta+k", "event": "Menu"}]}"#).contains("modifier \"Meta\""));
+        assert!(why(r#"{"bindings": [{"key": "k", "event": "Jump"}]}"#).contains("Jump"));
+        assert_eq!(
+            why(r#"{"bindings": [{"key": "m", "event": "Menu"}, {"key": "m", "event": "Tab"}]}"#),
+            "m is bound twice, to Menu and Tab"
+        );
+        assert_eq!(
+            why(r#"{"bindings": [{"key": "Ctrl+c", "event": "Cancel"}]}"#),
+            "no key is bound to Exit"
+        );
+    }
+
+    #[test]
+    fn test_conflicting_layouts() {
+        assert!(Keybindings::from_layouts(&["arrows", "vi", "wasd", "numpad"]).is_ok());
+        match Keybindings::from_layouts(&["numpad", "counts"]) {
+            Err(Gremlin::InvalidKeybindings(why)) => {
+                assert!(why.starts_with("layouts \"numpad\" and \"counts\" both bind"))
+            }
+            other => panic!("expected InvalidKeybindings, got {:?}", other),
+        }
+        assert!(Keybindings::from_layouts(&["wasd", "wasd"]).is_ok());
+
+        let roguelike = vec![(KeyChord::ch('q'), InputEvent::Menu)];
+        match Keybindings::merge(vec![("wasd", layout("wasd").unwrap()), ("roguelike", roguelike)]) {
+            Err(Gremlin::InvalidKeybindings(why)) => assert_eq!(
+                why,
+                "layouts \"wasd\" and \"roguelike\" both bind q, to Wasd(NW) and Menu"
+            ),
+            other => panic!("expected InvalidKeybindings, got {:?}", other),
+        }
+    }
+}
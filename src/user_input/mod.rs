@@ -0,0 +1,142 @@
+//Jerome M. St.Martin
+//May, 2022
+
+use std::time::Duration;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind};
+
+use super::error::Gremlin;
+use super::common::{Dir, InputEvent};
+
+mod keybindings;
+
+pub use keybindings::Keybindings;
+
+///Keys are looked up in the keybindings, except in text entry, where they are
+///typed as they are: Char, Backspace, Delete, Home, End, Confirm and Cancel,
+///with the arrow keys as Hjkl.
+#[derive(Debug, Default)]
+pub struct UserInput {
+    keybindings: Keybindings,
+    text_entry: bool, //Set by the TUI, through the Controller, while something is being typed.
+}
+
+impl UserInput {
+    pub fn new(keybindings: Keybindings) -> Self {
+        UserInput {
+            keybindings,
+            text_entry: false,
+        }
+    }
+
+    pub(crate) fn set_text_entry(&mut self, on: bool) {
+        self.text_entry = on;
+    }
+
+    #[cfg(test)]
+    pub(crate) fn text_entry(&self) -> bool {
+        self.text_entry
+    }
+
+    ///None if nothing arrived in time.
+    pub(crate) fn read_timeout(&self, timeout: Duration) -> Result<Option<InputEvent>, Gremlin> {
+
+        if !crossterm::event::poll(timeout)? {
+            return Ok(None);
+        }
+        let event = crossterm::event::read()?;
+
+        Ok(Some(self.translate(event)))
+    }
+
+    fn translate(&self, event: Event) -> InputEvent {
+        match event {
+            Event::Key(key_event) if self.text_entry => self.translate_text(key_event),
+            Event::Key(key_event) => self.keybindings.translate(key_event),
+            Event::Mouse(mouse_event) => {
+                let (column, row) = (mouse_event.column, mouse_event.row);
+                match mouse_event.kind {
+                    MouseEventKind::Down(MouseButton::Left) => InputEvent::Click { column, row },
+                    MouseEventKind::Moved => InputEvent::Hover { column, row },
+                    _ => InputEvent::Null,
+                }
+            },
+            Event::Resize(columns, rows) => InputEvent::Resize { columns, rows },
+        }
+    }
+
+    //Keys as typed, plus the few a line editor needs. Whatever is bound to Exit
+    //still exits, unless it could also be typed.
+    fn translate_text(&self, key_event: KeyEvent) -> InputEvent {
+        let typed = !key_event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key_event.code {
+            KeyCode::Char(c) if typed => InputEvent::Char(c),
+            KeyCode::Enter => InputEvent::Confirm,
+            KeyCode::Esc => InputEvent::Cancel,
+            KeyCode::Backspace => InputEvent::Backspace,
+            KeyCode::Delete => InputEvent::Delete,
+            KeyCode::Left => InputEvent::Hjkl(Dir::W),
+            KeyCode::Right => InputEvent::Hjkl(Dir::E),
+            KeyCode::Up => InputEvent::Hjkl(Dir::N),
+            KeyCode::Down => InputEvent::Hjkl(Dir::S),
+            KeyCode::Home => InputEvent::Home,
+            KeyCode::End => InputEvent::End,
+            _ if !typed && self.keybindings.translate(key_event) == InputEvent::Exit => InputEvent::Exit,
+            _ => InputEvent::Null,
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use crossterm::event::MouseEvent;
+
+    use super::*;
+
+    fn mouse(kind: MouseEventKind) -> Event {
+        Event::Mouse(MouseEvent {
+            kind,
+            column: 4,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    #[test]
+    fn test_translate_mouse() {
+        let user_input = UserInput::default();
+        assert_eq!(
+            user_input.translate(mouse(MouseEventKind::Down(MouseButton::Left))),
+            InputEvent::Click { column: 4, row: 2 }
+        );
+        assert_eq!(user_input.translate(mouse(MouseEventKind::Moved)), InputEvent::Hover { column: 4, row: 2 });
+        assert_eq!(user_input.translate(mouse(MouseEventKind::Down(MouseButton::Right))), InputEvent::Null);
+        assert_eq!(user_input.translate(mouse(MouseEventKind::ScrollUp)), InputEvent::Null);
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn test_text_entry() {
+        let mut user_input = UserInput::default();
+        assert_eq!(user_input.translate(key(KeyCode::Char('k'), KeyModifiers::NONE)), InputEvent::Hjkl(Dir::N));
+
+        user_input.set_text_entry(true);
+        assert_eq!(user_input.translate(key(KeyCode::Char('k'), KeyModifiers::NONE)), InputEvent::Char('k'));
+        assert_eq!(user_input.translate(key(KeyCode::Char('K'), KeyModifiers::SHIFT)), InputEvent::Char('K'));
+        assert_eq!(user_input.translate(key(KeyCode::Backspace, KeyModifiers::NONE)), InputEvent::Backspace);
+        assert_eq!(user_input.translate(key(KeyCode::Left, KeyModifiers::NONE)), InputEvent::Hjkl(Dir::W));
+        assert_eq!(user_input.translate(key(KeyCode::Enter, KeyModifiers::NONE)), InputEvent::Confirm);
+        assert_eq!(user_input.translate(key(KeyCode::F(1), KeyModifiers::NONE)), InputEvent::Null);
+        assert_eq!(user_input.translate(key(KeyCode::Char('c'), KeyModifiers::CONTROL)), InputEvent::Exit);
+        assert_eq!(user_input.translate(key(KeyCode::Char('b'), KeyModifiers::ALT)), InputEvent::Null);
+        assert_eq!(user_input.translate(mouse(MouseEventKind::Moved)), InputEvent::Hover { column: 4, row: 2 });
+
+        user_input.set_text_entry(false);
+        assert_eq!(user_input.translate(key(KeyCode::Backspace, KeyModifiers::NONE)), InputEvent::Delete);
+    }
+}